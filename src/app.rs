@@ -1,13 +1,22 @@
+use crate::common::icons::IconSet;
 use crate::common::sort;
+use crate::utils::dir::PermsStyle;
+use crate::utils::format::{self, ByteSize, SizeUnit};
+use anyhow::Context;
 use chrono::{Duration, NaiveDate, Utc};
-use clap::{Parser, ValueEnum};
-use serde::Deserialize;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-#[derive(Parser, Debug, Deserialize)]
+#[derive(Parser, Debug, Deserialize, Serialize)]
+#[serde(default)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 #[command(override_usage = "wisu [OPTIONS] [PATH]")]
@@ -31,17 +40,95 @@ pub struct Args {
     #[arg(long)]
     pub config: Option<PathBuf>,
 
-    /// Path to the directory to explore/display
+    /// Path to the directory to explore/display (defaults to `$WISU_PATH`, then ".")
     #[arg(default_value = ".")]
     pub path: PathBuf,
 
+    /// Show a symlinked root by its resolved path; the root is listed either way, inner links are not followed
+    #[arg(long)]
+    pub dereference_args: bool,
+
+    /// Hide the loaded config file from the listing
+    #[arg(long)]
+    pub hide_config: bool,
+
+    /// Print the effective configuration (config file merged with the CLI) as TOML and exit
+    #[arg(long)]
+    #[serde(skip)]
+    pub print_config: bool,
+
+    /// Descend into symlinked directories outside the tree; links within it are listed without descending
+    #[arg(long)]
+    pub follow_symlinks: bool,
+
+    /// Leave symlinks out of the listing and the counts
+    #[arg(long)]
+    pub ignore_symlinks: bool,
+
+    /// List the contents of zip archives as virtual subtrees
+    #[arg(long)]
+    pub into_archives: bool,
+
+    /// Canonical path of the config file that was loaded, if any
+    #[arg(skip)]
+    #[serde(skip)]
+    pub loaded_config: Option<PathBuf>,
+
+    /// `[tui]` section of the config file
+    #[arg(skip)]
+    pub tui: TuiConfig,
+
+    /// `[keys]` section of the config file
+    #[arg(skip)]
+    pub keys: KeyMap,
+
     /* =========================
      * Output / export
      * ========================= */
-    /// Export output format (json, csv, xml)
-    #[arg(short = 'o', default_value = None, value_parser = clap::builder::PossibleValuesParser::new(["json", "csv", "xml"]))]
+    /// Output layout (tree, table, long) or export format (json, xml, csv, tsv, yaml, ndjson; see --output)
+    #[arg(long, value_enum, default_value_t = Format::Tree)]
+    pub format: Format,
+
+    /// One line per entry with permissions, size, mtime and path (`--format long`)
+    #[arg(short = 'n', long)]
+    pub long: bool,
+
+    /// Export to `export.<TYPE>` (json, csv, tsv, xml, yaml, ndjson); same as `--format TYPE --output export.TYPE`
+    #[arg(short = 'o', default_value = None, value_parser = clap::builder::PossibleValuesParser::new(["json", "csv", "tsv", "xml", "yaml", "yml", "ndjson", "jsonl"]))]
     pub out: Option<String>,
 
+    /// Write the `--format json|xml|csv|tsv|yaml|ndjson` export to PATH instead of stdout
+    #[arg(long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Cut hierarchical exports below this depth, marking cut directories `truncated`
+    #[arg(long, value_name = "DEPTH")]
+    pub export_depth: Option<usize>,
+
+    /// Write export paths relative to BASE (absolute for entries outside it)
+    #[arg(long, value_name = "BASE")]
+    pub relative_to: Option<PathBuf>,
+
+    /// Write JSON and CSV exports node by node; JSON becomes a flat array of entries
+    #[arg(long)]
+    pub stream: bool,
+
+    /// Add a per-extension count/size summary to JSON exports
+    #[arg(long)]
+    pub group_by_ext: bool,
+
+    /// Write a SHA-256 manifest of all listed files to PATH
+    #[arg(long, value_name = "PATH", conflicts_with = "checksum_manifest")]
+    pub checksums: Option<PathBuf>,
+
+    /// Compare the tree against a SHA-256 manifest, exiting non-zero on differences
+    #[arg(long, value_name = "PATH")]
+    pub checksum_manifest: Option<PathBuf>,
+
+    /// Report sets of files with identical content and the space they waste
+    #[arg(long)]
+    pub dedupe_by_content: bool,
+
     /* =========================
      * Content filters
      * ========================= */
@@ -57,14 +144,26 @@ pub struct Args {
     #[arg(short = 'a', long)]
     pub all: bool,
 
+    /// Exclude entries starting with `.`, even with --all
+    #[arg(long)]
+    pub no_dotfiles: bool,
+
     /// Respect .gitignore and other ignore files
     #[arg(short = 'g', long)]
     pub gitignore: bool,
 
+    /// Read extra ignore patterns from a gitignore-style file (repeatable)
+    #[arg(long = "ignore-file", value_name = "PATH")]
+    pub ignore_file: Vec<PathBuf>,
+
     /// Exclude files by extension (comma-separated, e.g. "log,tmp")
     #[arg(short = 'e', long)]
     pub exclude: Option<String>,
 
+    /// Keep only files with these extensions (comma-separated, e.g. "rs,toml")
+    #[arg(long, value_name = "EXTS")]
+    pub only_ext: Option<String>,
+
     /// Time filter (relative or absolute date)
     ///
     /// Relative: 5d, 2w, 3M, 1y, 30s, 10m
@@ -74,6 +173,10 @@ pub struct Args {
     #[arg(short = 't', long)]
     pub time: Option<TimeFilter>,
 
+    /// Only show files added or modified since a git commit/ref
+    #[arg(long, value_name = "REF")]
+    pub since_commit: Option<String>,
+
     /* =========================
      * Depth & limits
      * ========================= */
@@ -81,14 +184,47 @@ pub struct Args {
     #[arg(long)]
     pub expand_level: Option<usize>,
 
-    /// Maximum directory depth (non-interactive only)
-    #[arg(short = 'L', long)]
-    pub level: Option<usize>,
+    /// Maximum directory depth (non-interactive only); negative counts from the leaves,
+    /// e.g. -1 shows only the deepest level
+    #[arg(short = 'L', long, allow_negative_numbers = true)]
+    pub level: Option<isize>,
+
+    /// Walk directories with N threads (0 picks a count automatically); not with --max-total-size or --sort none
+    #[arg(long, value_name = "N")]
+    pub threads: Option<usize>,
 
-    /// Maximum number of files (non-interactive only)
+    /// List only the immediate children of the root, as a flat list
+    #[arg(long)]
+    pub no_recursion: bool,
+
+    /// Maximum number of files per directory
     #[arg(short = 'F', long)]
     pub files: Option<usize>,
 
+    /// Collapse directories larger than SIZE (e.g. 500M, 2G) into one summary line
+    #[arg(long, value_name = "SIZE")]
+    pub max_size_per_dir: Option<ByteSize>,
+
+    /// Only show files of at least SIZE (e.g. 500k, 10M, 2KiB or plain bytes)
+    #[arg(long, value_name = "SIZE")]
+    pub min_size: Option<ByteSize>,
+
+    /// Only show files of at most SIZE (e.g. 500k, 10M, 2KiB or plain bytes)
+    #[arg(long, value_name = "SIZE")]
+    pub max_size: Option<ByteSize>,
+
+    /// Hide files larger than SIZE; directories are always kept
+    #[arg(long, value_name = "SIZE")]
+    pub exclude_larger_than: Option<ByteSize>,
+
+    /// Hide files smaller than SIZE; directories are always kept
+    #[arg(long, value_name = "SIZE")]
+    pub exclude_smaller_than: Option<ByteSize>,
+
+    /// Stop the scan once the listed files add up to SIZE, printing a partial tree
+    #[arg(long, value_name = "SIZE")]
+    pub max_total_size: Option<ByteSize>,
+
     /* =========================
      * Sorting
      * ========================= */
@@ -96,10 +232,22 @@ pub struct Args {
     #[arg(long, default_value_t = SortType::Name)]
     pub sort: SortType,
 
+    /// Sort directories by this criterion instead of --sort (`size` totals their contents)
+    #[arg(long, value_name = "TYPE")]
+    pub sort_dirs_by: Option<SortType>,
+
+    /// Sort files by this criterion instead of --sort
+    #[arg(long, value_name = "TYPE")]
+    pub sort_files_by: Option<SortType>,
+
     /// Reverse sort order
     #[arg(short = 'r', long)]
     pub reverse: bool,
 
+    /// Use the latest modification time among descendants for directories
+    #[arg(long)]
+    pub recursive_mtime: bool,
+
     /// Sort directories before files
     #[arg(long)]
     pub dirs_first: bool,
@@ -127,6 +275,38 @@ pub struct Args {
     #[arg(long)]
     pub icons: bool,
 
+    /// Icon glyphs: emoji, or Nerd Font glyphs that stay aligned (requires a Nerd Font)
+    #[arg(long, value_enum, default_value_t = IconSet::Emoji)]
+    pub icon_set: IconSet,
+
+    /// Display icons for directories only, leaving files iconless
+    #[arg(long)]
+    pub icons_only_dirs: bool,
+
+    /// Indent by depth only, without tree connector glyphs
+    #[arg(long)]
+    pub no_connector: bool,
+
+    /// Print one path relative to the root per line, without tree art (like `find`)
+    #[arg(long)]
+    pub flat: bool,
+
+    /// Draw tree connectors with ASCII characters (`|--`, `` `-- ``, `|`)
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Prefix each entry with its depth, e.g. `[2] ├── file`
+    #[arg(long)]
+    pub depth_markers: bool,
+
+    /// Fold chains of single-child directories into one line (`a/b/c`)
+    #[arg(long)]
+    pub collapse_single_child: bool,
+
+    /// Print a legend of the color/icon conventions after the tree
+    #[arg(long)]
+    pub legend: bool,
+
     /* =========================
      * Metadata & details
      * ========================= */
@@ -134,52 +314,159 @@ pub struct Args {
     #[arg(short = 's', long)]
     pub size: bool,
 
+    /// With --info, annotate files only: no sizes or counts on directories
+    #[arg(long)]
+    pub no_size_for_dirs: bool,
+
+    /// With --info, show each size as a percentage of its parent directory
+    #[arg(long)]
+    pub percent: bool,
+
+    /// Report allocated disk usage instead of apparent sizes (Unix; elsewhere apparent sizes)
+    #[arg(long)]
+    pub disk_usage: bool,
+
+    /// Show every size as a plain byte count; the stats total adds the raw count to the human one
+    #[arg(long)]
+    pub bytes: bool,
+
+    /// Show sizes with decimal SI prefixes (kB, MB, GB at 1000) instead of binary ones
+    #[arg(long)]
+    pub si: bool,
+
+    /// Show every size in a single unit (B, KiB, MiB, GiB, TiB)
+    #[arg(long, value_name = "UNIT", ignore_case = true)]
+    pub size_in: Option<SizeUnit>,
+
     /// Show file permissions
     #[arg(short = 'p', long)]
     pub permissions: bool,
 
+    /// Show permissions in octal (`755`) instead of `rwxr-xr-x`
+    #[arg(long)]
+    pub perms_octal: bool,
+
+    /// Show owner and group names, like `ls -l` (Unix; numeric ids when a name is unknown)
+    #[arg(long)]
+    pub owner: bool,
+
+    /// Color the read/write/execute flags of permissions instead of dimming them
+    #[arg(long)]
+    pub color_perms: bool,
+
+    /// Color entries by git status: untracked green, ignored dimmed
+    #[arg(long)]
+    pub git_status: bool,
+
     /// Show extended directory info
     #[arg(short = 'x', long, default_value = "false")]
     pub info: bool,
 
+    /// Columns to display and their order (comma-separated: perms,owner,size,mtime,name)
+    #[arg(long, value_delimiter = ',')]
+    pub columns: Option<Vec<Column>>,
+
     /// Show scan statistics
     #[arg(long, default_value = "true")]
     pub stats: bool,
+
+    /// Scan everything but print only the root with its recursive totals
+    #[arg(long)]
+    pub root_only_stats: bool,
+
+    /// Also write the stats (counts, size, duration, throughput, entries per depth) as JSON to PATH, for exports too
+    #[arg(long, value_name = "PATH")]
+    pub stats_json: Option<PathBuf>,
+
+    /// Exit with status 1 when no files (directories with -d) are listed, like `grep`; not in the TUI or with --watch
+    #[arg(long)]
+    pub fail_if_empty: bool,
+}
+
+/// Options missing from a config file take their CLI defaults
+impl Default for Args {
+    fn default() -> Self {
+        Args::parse_from(["wisu"])
+    }
 }
 
 impl Args {
     /// Load `Args` from CLI + TOML file (if it exists).
     /// CLI values override those from the file.
-    pub fn load() -> Self {
-        let cli_args = Args::parse(); // read CLI
+    pub fn load() -> anyhow::Result<Self> {
+        let matches = Args::command().get_matches(); // read CLI
+        let mut cli_args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+        // `WISU_PATH` replaces the "." default, never an explicit path
+        if matches.value_source("path") == Some(ValueSource::DefaultValue)
+            && let Some(path) = std::env::var_os("WISU_PATH").filter(|p| !p.is_empty())
+        {
+            cli_args.path = PathBuf::from(path);
+        }
 
-        if let Some(config_path) = cli_args.config.clone() {
-            if let Some(mut file_args) = Self::from_file(&config_path) {
-                file_args = Self::merge(file_args, cli_args);
-                return file_args;
+        // Layers, lowest precedence first: the global config, then `--config`
+        // (or else `wisu.toml` in the provided path); CLI flags override both
+        let mut layered: Option<(Args, PathBuf)> = None;
+        let project = cli_args
+            .config
+            .clone()
+            .filter(|path| path.exists())
+            .unwrap_or_else(|| cli_args.path.join("wisu.toml"));
+
+        for path in Self::global_config_path().into_iter().chain([project]) {
+            if let Some(file_args) = Self::from_file(&path)? {
+                layered = Some(match layered {
+                    Some((lower, _)) => (Self::merge(lower, file_args), path),
+                    None => (file_args, path),
+                });
             }
         }
 
-        // Otherwise, look for `wisu.toml` in the provided path
-        let candidate = cli_args.path.join("wisu.toml");
-        if let Some(mut file_args) = Self::from_file(&candidate) {
-            file_args = Self::merge(file_args, cli_args);
-            return file_args;
-        }
+        let args = match layered {
+            Some((file_args, path)) => Self::merge(file_args, cli_args).with_loaded_config(&path),
+            None => cli_args,
+        };
+        // Checked once the layers are merged, since an upper layer may free a key
+        args.keys.validate()?;
+        Ok(args)
+    }
 
-        cli_args
+    /// `wisu/wisu.toml` under `$XDG_CONFIG_HOME` (default `~/.config`), or `%APPDATA%` on Windows
+    fn global_config_path() -> Option<PathBuf> {
+        let non_empty = |var| std::env::var_os(var).filter(|v| !v.is_empty()).map(PathBuf::from);
+        let base = if cfg!(windows) {
+            non_empty("APPDATA")
+        } else {
+            non_empty("XDG_CONFIG_HOME").or_else(|| non_empty("HOME").map(|h| h.join(".config")))
+        };
+        Some(base?.join("wisu").join("wisu.toml"))
     }
 
-    fn from_file(path: &Path) -> Option<Self> {
+    fn with_loaded_config(mut self, path: &Path) -> Self {
+        self.loaded_config = Some(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+        self
+    }
+
+    fn from_file(path: &Path) -> anyhow::Result<Option<Self>> {
         if !path.exists() {
-            return None;
+            return Ok(None);
         }
-        let content = fs::read_to_string(path).ok()?;
-        toml::from_str::<Args>(&content).ok()
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let args = toml::from_str::<Args>(&content)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        Ok(Some(args))
     }
 
     /// Merge two Args: CLI values override those from the file
     fn merge(mut file: Args, cli: Args) -> Args {
+        // Bindings and expansion rules of a higher layer add to (or replace) those below
+        file.keys.0.extend(cli.keys.0);
+        file.tui.expand.extend(cli.tui.expand);
+        if cli.tui.search.is_some() {
+            file.tui.search = cli.tui.search;
+        }
+
         // Optional options
         if cli.out.is_some() {
             file.out = cli.out;
@@ -202,9 +489,69 @@ impl Args {
         if cli.exclude.is_some() {
             file.exclude = cli.exclude;
         }
+        if cli.only_ext.is_some() {
+            file.only_ext = cli.only_ext;
+        }
+        if cli.format != Format::Tree {
+            file.format = cli.format;
+        }
+        if cli.icon_set != IconSet::Emoji {
+            file.icon_set = cli.icon_set;
+        }
+        if cli.since_commit.is_some() {
+            file.since_commit = cli.since_commit;
+        }
+        if cli.columns.is_some() {
+            file.columns = cli.columns;
+        }
+        if cli.size_in.is_some() {
+            file.size_in = cli.size_in;
+        }
+        if cli.max_size_per_dir.is_some() {
+            file.max_size_per_dir = cli.max_size_per_dir;
+        }
+        if cli.min_size.is_some() {
+            file.min_size = cli.min_size;
+        }
+        if cli.max_size.is_some() {
+            file.max_size = cli.max_size;
+        }
+        if cli.exclude_larger_than.is_some() {
+            file.exclude_larger_than = cli.exclude_larger_than;
+        }
+        if cli.exclude_smaller_than.is_some() {
+            file.exclude_smaller_than = cli.exclude_smaller_than;
+        }
+        if cli.threads.is_some() {
+            file.threads = cli.threads;
+        }
+        if cli.max_total_size.is_some() {
+            file.max_total_size = cli.max_total_size;
+        }
+        if cli.output.is_some() {
+            file.output = cli.output;
+        }
+        if cli.stats_json.is_some() {
+            file.stats_json = cli.stats_json;
+        }
+        if cli.export_depth.is_some() {
+            file.export_depth = cli.export_depth;
+        }
+        if cli.relative_to.is_some() {
+            file.relative_to = cli.relative_to;
+        }
+        if cli.checksums.is_some() {
+            file.checksums = cli.checksums;
+        }
+        if cli.checksum_manifest.is_some() {
+            file.checksum_manifest = cli.checksum_manifest;
+        }
+
+        // Ignore files stack on top of those from the config
+        file.ignore_file.extend(cli.ignore_file);
 
         // Path (if different from default)
-        if cli.path != PathBuf::from(".") {
+        if cli.path != Path::new(".") {
             file.path = cli.path;
         }
 
@@ -219,48 +566,103 @@ impl Args {
 
         merge_flag!(interactive);
         merge_flag!(watch);
+        merge_flag!(group_by_ext);
+        merge_flag!(stream);
+        merge_flag!(hide_config);
+        merge_flag!(print_config);
+        merge_flag!(dedupe_by_content);
+        merge_flag!(dereference_args);
+        merge_flag!(ignore_symlinks);
+        merge_flag!(follow_symlinks);
+        merge_flag!(into_archives);
         merge_flag!(dirs_only);
         merge_flag!(info);
         merge_flag!(stats);
+        merge_flag!(root_only_stats);
+        merge_flag!(fail_if_empty);
         merge_flag!(hyperlinks);
         merge_flag!(all);
+        merge_flag!(no_dotfiles);
         merge_flag!(gitignore);
         merge_flag!(icons);
+        merge_flag!(icons_only_dirs);
+        merge_flag!(legend);
+        merge_flag!(color_perms);
+        merge_flag!(git_status);
+        merge_flag!(disk_usage);
+        merge_flag!(si);
+        merge_flag!(bytes);
+        merge_flag!(no_connector);
+        merge_flag!(ascii);
+        merge_flag!(flat);
+        merge_flag!(depth_markers);
+        merge_flag!(collapse_single_child);
+        merge_flag!(no_recursion);
+        merge_flag!(long);
         merge_flag!(size);
+        merge_flag!(percent);
+        merge_flag!(no_size_for_dirs);
         merge_flag!(permissions);
+        merge_flag!(owner);
+        merge_flag!(perms_octal);
         merge_flag!(files_only);
         merge_flag!(dirs_first);
         merge_flag!(case_sensitive);
         merge_flag!(natural_sort);
         merge_flag!(reverse);
+        merge_flag!(recursive_mtime);
         merge_flag!(dotfiles_first);
 
-        // Enum or other fields with defaults
-        file.sort = cli.sort;
+        // Enum or other fields with defaults: only a non-default CLI value overrides
+        if cli.sort != SortType::Name {
+            file.sort = cli.sort;
+        }
+        if cli.sort_dirs_by.is_some() {
+            file.sort_dirs_by = cli.sort_dirs_by;
+        }
+        if cli.sort_files_by.is_some() {
+            file.sort_files_by = cli.sort_files_by;
+        }
 
         file
     }
 
     /// Get the set of excluded extensions (normalized to lowercase)
     pub fn get_excluded_extensions(&self) -> std::collections::HashSet<String> {
-        let mut excluded = std::collections::HashSet::new();
-
-        if let Some(ref exclude_str) = self.exclude {
-            for ext in exclude_str.split(',') {
-                let normalized = ext.trim().to_lowercase();
-                if !normalized.is_empty() {
-                    // Remove leading dot if present
-                    let clean_ext = if normalized.starts_with('.') {
-                        normalized[1..].to_string()
-                    } else {
-                        normalized
-                    };
-                    excluded.insert(clean_ext);
-                }
-            }
-        }
+        self.exclude.as_deref().map(parse_extensions).unwrap_or_default()
+    }
+
+    /// Extensions kept by `--only-ext` (normalized to lowercase), if given
+    pub fn only_extensions(&self) -> Option<std::collections::HashSet<String>> {
+        self.only_ext.as_deref().map(parse_extensions)
+    }
+
+    /// Permission spelling chosen by `--perms-octal`
+    pub fn perms_style(&self) -> PermsStyle {
+        if self.perms_octal { PermsStyle::Octal } else { PermsStyle::Symbolic }
+    }
+
+    /// Whether an entry gets an icon, given `--icons` and `--icons-only-dirs`
+    pub fn shows_icon(&self, is_dir: bool) -> bool {
+        if self.icons_only_dirs { is_dir } else { self.icons }
+    }
 
-        excluded
+    /// Check if a column was requested with `--columns` (or is shown by default by the table)
+    pub fn has_column(&self, column: Column) -> bool {
+        match &self.columns {
+            Some(columns) => columns.contains(&column),
+            None => match self.format {
+                Format::Tree
+                | Format::Json
+                | Format::Xml
+                | Format::Csv
+                | Format::Tsv
+                | Format::Yaml
+                | Format::Ndjson => false,
+                Format::Table => TABLE_COLUMNS.contains(&column),
+                Format::Long => LONG_COLUMNS.contains(&column),
+            },
+        }
     }
 
     /// Check if a file should be excluded based on its extension
@@ -271,16 +673,33 @@ impl Args {
 
         let excluded = self.get_excluded_extensions();
 
-        if let Some(ext) = path.extension() {
-            if let Some(ext_str) = ext.to_str() {
-                return excluded.contains(&ext_str.to_lowercase());
-            }
+        if let Some(ext) = path.extension()
+            && let Some(ext_str) = ext.to_str()
+        {
+            return excluded.contains(&ext_str.to_lowercase());
         }
 
         false
     }
 }
 
+/// Parses a comma-separated extension list, lowercased and without leading dots
+fn parse_extensions(list: &str) -> std::collections::HashSet<String> {
+    let mut extensions = std::collections::HashSet::new();
+    for ext in list.split(',') {
+        let normalized = ext.trim().to_lowercase();
+        if !normalized.is_empty() {
+            // Remove leading dot if present
+            let clean_ext = match normalized.strip_prefix('.') {
+                Some(stripped) => stripped.to_string(),
+                None => normalized,
+            };
+            extensions.insert(clean_ext);
+        }
+    }
+    extensions
+}
+
 /// Represents a time-based filter for files
 #[derive(Debug, Clone, Deserialize)]
 #[serde(try_from = "String")]
@@ -317,10 +736,10 @@ impl FromStr for TimeFilter {
         let now = Utc::now();
 
         // Check for before (<) or after (>) prefix
-        let (mode, date_part) = if s.starts_with('<') {
-            (TimeFilterMode::Before, &s[1..])
-        } else if s.starts_with('>') {
-            (TimeFilterMode::After, &s[1..])
+        let (mode, date_part) = if let Some(rest) = s.strip_prefix('<') {
+            (TimeFilterMode::Before, rest)
+        } else if let Some(rest) = s.strip_prefix('>') {
+            (TimeFilterMode::After, rest)
         } else {
             (TimeFilterMode::After, s) // default: after
         };
@@ -387,6 +806,12 @@ impl TryFrom<String> for TimeFilter {
     }
 }
 
+impl Serialize for TimeFilter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl fmt::Display for TimeFilter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let prefix = match self.mode {
@@ -398,6 +823,7 @@ impl fmt::Display for TimeFilter {
 }
 
 #[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Default, Deserialize)]
+#[serde(try_from = "String")]
 pub enum SortType {
     #[default]
     Name,
@@ -406,6 +832,25 @@ pub enum SortType {
     Created,
     Modified,
     Extension,
+    /// Number of direct children the scan lists, as `--info` counts them (directories only)
+    Children,
+    /// Keep the filesystem walk order
+    None,
+}
+
+/// Accepts the same (case-insensitive) names as `--sort`
+impl TryFrom<String> for SortType {
+    type Error = String;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        <SortType as ValueEnum>::from_str(&s, true).map_err(|_| {
+            let valid: Vec<String> = SortType::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect();
+            format!("invalid sort value '{}' (valid options: {})", s, valid.join(", "))
+        })
+    }
 }
 
 impl From<SortType> for sort::SortType {
@@ -417,11 +862,251 @@ impl From<SortType> for sort::SortType {
             SortType::Created => sort::SortType::Created,
             SortType::Modified => sort::SortType::Modified,
             SortType::Extension => sort::SortType::Extension,
+            SortType::Children => sort::SortType::Children,
+            SortType::None => sort::SortType::None,
+        }
+    }
+}
+
+/// `[tui]` section of the config file
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TuiConfig {
+    /// Directory name or glob → levels to expand below it (0 keeps it collapsed)
+    pub expand: BTreeMap<String, usize>,
+    /// Initial search matcher (switched with Ctrl+F while searching); substring when unset
+    pub search: Option<SearchMatcher>,
+}
+
+/// TUI actions bound to a single key, remappable under `[keys]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    Quit,
+    Search,
+    Refresh,
+    Up,
+    Down,
+    Bottom,
+    ExpandAll,
+    CollapseAll,
+    Delete,
+    Preview,
+    Copy,
+    Reveal,
+}
+
+impl KeyAction {
+    const ALL: [Self; 12] = [
+        Self::Quit,
+        Self::Search,
+        Self::Refresh,
+        Self::Up,
+        Self::Down,
+        Self::Bottom,
+        Self::ExpandAll,
+        Self::CollapseAll,
+        Self::Delete,
+        Self::Preview,
+        Self::Copy,
+        Self::Reveal,
+    ];
+
+    /// Key used unless `[keys]` rebinds the action
+    fn default_key(self) -> char {
+        match self {
+            Self::Quit => 'q',
+            Self::Search => '/',
+            Self::Refresh => 'r',
+            Self::Up => 'k',
+            Self::Down => 'j',
+            Self::Bottom => 'G',
+            Self::ExpandAll => 'E',
+            Self::CollapseAll => 'C',
+            Self::Delete => 'd',
+            Self::Preview => 'p',
+            Self::Copy => 'y',
+            Self::Reveal => 'o',
+        }
+    }
+}
+
+impl fmt::Display for KeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Quit => "quit",
+            Self::Search => "search",
+            Self::Refresh => "refresh",
+            Self::Up => "up",
+            Self::Down => "down",
+            Self::Bottom => "bottom",
+            Self::ExpandAll => "expand_all",
+            Self::CollapseAll => "collapse_all",
+            Self::Delete => "delete",
+            Self::Preview => "preview",
+            Self::Copy => "copy",
+            Self::Reveal => "reveal",
+        }
+        .fmt(f)
+    }
+}
+
+/// `[keys]` section of the config file: action name → key, e.g. `quit = "x"`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct KeyMap(BTreeMap<KeyAction, char>);
+
+impl KeyMap {
+    /// Key bound to `action`
+    pub fn key(&self, action: KeyAction) -> char {
+        self.0.get(&action).copied().unwrap_or_else(|| action.default_key())
+    }
+
+    /// Action bound to `key`. Rebound actions give up their default key, and a
+    /// rebinding takes the key from the action that had it by default.
+    pub fn action(&self, key: char) -> Option<KeyAction> {
+        let rebound = self.0.iter().find(|&(_, &bound)| bound == key).map(|(&action, _)| action);
+        rebound.or_else(|| {
+            KeyAction::ALL
+                .into_iter()
+                .find(|action| !self.0.contains_key(action) && action.default_key() == key)
+        })
+    }
+
+    /// Rejects two actions rebound to the same key, and `g`, which the TUI
+    /// keeps for `gg` (jump to the top)
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut bound: BTreeMap<char, KeyAction> = BTreeMap::new();
+        for (&action, &key) in &self.0 {
+            if key == 'g' {
+                anyhow::bail!(
+                    "[keys] {action} = \"g\": `g` is reserved for `gg` (jump to the top)"
+                );
+            }
+            if let Some(other) = bound.insert(key, action) {
+                anyhow::bail!("[keys] {other} and {action} are both bound to \"{key}\"");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How TUI search queries match entry names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMatcher {
+    #[default]
+    Substring,
+    Prefix,
+    /// Query characters appear in order, not necessarily adjacent; best matches first
+    Fuzzy,
+}
+
+impl SearchMatcher {
+    /// Next matcher in the Ctrl+F cycle
+    pub fn next(self) -> Self {
+        match self {
+            Self::Substring => Self::Prefix,
+            Self::Prefix => Self::Fuzzy,
+            Self::Fuzzy => Self::Substring,
+        }
+    }
+
+    /// Match `query` against `name` (both already lowercased), returning a score (higher is
+    /// better, always 0 for substring and prefix) and the char indices of the matched characters
+    pub fn find(self, name: &str, query: &str) -> Option<(i64, Vec<usize>)> {
+        let contiguous = |byte_start: usize| {
+            let start = name[..byte_start].chars().count();
+            (0, (start..start + query.chars().count()).collect())
+        };
+
+        match self {
+            Self::Substring => name.find(query).map(contiguous),
+            Self::Prefix => name.starts_with(query).then(|| contiguous(0)),
+            Self::Fuzzy => SkimMatcherV2::default().fuzzy_indices(name, query),
         }
     }
 }
 
+impl fmt::Display for SearchMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Substring => "substring",
+            Self::Prefix => "prefix",
+            Self::Fuzzy => "fuzzy",
+        }
+        .fmt(f)
+    }
+}
+
+/// Layout of the non-interactive output, selectable with `--format`
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Tree,
+    Table,
+    /// `ls -lR`-like: one unconnected line per entry, no header
+    Long,
+    /// Exports, written to `--output` or stdout
+    Json,
+    Xml,
+    Csv,
+    /// CSV columns separated by tabs, unquoted
+    Tsv,
+    #[value(alias = "yml")]
+    Yaml,
+    /// One JSON object per entry and line
+    #[value(alias = "jsonl")]
+    Ndjson,
+}
+
+/// Columns of `--format table` when `--columns` is not given
+pub const TABLE_COLUMNS: &[Column] = &[Column::Name, Column::Size, Column::Perms, Column::Mtime];
+
+/// Columns of `--format long` when `--columns` is not given
+pub const LONG_COLUMNS: &[Column] = &[Column::Perms, Column::Size, Column::Mtime, Column::Name];
+
+/// A column of the classic view, selectable with `--columns`
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    #[value(alias = "permissions")]
+    Perms,
+    /// `user group`, as with `--owner`
+    Owner,
+    Size,
+    #[value(alias = "modified")]
+    Mtime,
+    Name,
+}
+
 impl Args {
+    /// Formats a size honoring `--bytes`, `--size-in` and `--si`
+    pub fn format_size(&self, bytes: u64) -> String {
+        if self.bytes { bytes.to_string() } else { self.format_size_human(bytes) }
+    }
+
+    /// Formats a size honoring `--size-in` and `--si`, even with `--bytes`
+    pub fn format_size_human(&self, bytes: u64) -> String {
+        match self.size_in {
+            Some(unit) => format::size_with(bytes, unit),
+            None if self.si => format::size_si(bytes),
+            None => format::size(bytes),
+        }
+    }
+
+    /// Whether a file of `bytes` passes every size filter; a file must satisfy all of them
+    pub fn size_allowed(&self, bytes: u64) -> bool {
+        let at_least = |min: Option<ByteSize>| min.is_none_or(|min| bytes >= min.0);
+        let at_most = |max: Option<ByteSize>| max.is_none_or(|max| bytes <= max.0);
+
+        at_least(self.min_size)
+            && at_most(self.max_size)
+            && at_least(self.exclude_smaller_than)
+            && at_most(self.exclude_larger_than)
+    }
+
     pub fn to_sort_options(&self) -> sort::SortOptions {
         sort::SortOptions {
             sort_type: self.sort.into(),
@@ -430,10 +1115,19 @@ impl Args {
             natural_sort: self.natural_sort,
             reverse: self.reverse,
             dotfiles_first: self.dotfiles_first,
+            recursive_mtime: self.recursive_mtime,
+            dirs_sort_type: self.sort_dirs_by.map(Into::into),
+            files_sort_type: self.sort_files_by.map(Into::into),
         }
     }
 }
 
+impl Serialize for SortType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl fmt::Display for SortType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.to_possible_value().expect("no values are skipped").get_name().fmt(f)
@@ -0,0 +1,121 @@
+//! Lists the contents of zip archives as virtual subtrees (`--into-archives`).
+
+use crate::common::tree::TreeEntry;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+
+/// Check if a file is an archive `--into-archives` can list
+pub fn is_archive(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+#[derive(Default)]
+struct Node {
+    is_dir: bool,
+    size: u64,
+    children: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn insert(&mut self, components: &[String], is_dir: bool, size: u64) {
+        let Some((first, rest)) = components.split_first() else { return };
+        let child = self.children.entry(first.clone()).or_default();
+        if rest.is_empty() {
+            child.is_dir |= is_dir;
+            child.size = size;
+        } else {
+            // Parents may only be implied by their files' names
+            child.is_dir = true;
+            child.insert(rest, is_dir, size);
+        }
+    }
+
+    /// Sums file sizes up into their directories
+    fn aggregate(&mut self) -> u64 {
+        if self.is_dir {
+            self.size = self.children.values_mut().map(Node::aggregate).sum();
+        }
+        self.size
+    }
+}
+
+/// Reads the central directory of the zip at `path` and returns its entries in
+/// tree order, one level below `depth`; their paths are `path` joined with the
+/// name inside the archive. Nothing is decompressed.
+pub fn list_zip(path: &Path, depth: usize) -> anyhow::Result<Vec<TreeEntry>> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+
+    let mut root = Node { is_dir: true, ..Default::default() };
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        // Skip names that would escape the archive (`../`, absolute paths)
+        let Some(name) = file.enclosed_name() else { continue };
+        let components: Vec<String> =
+            name.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+        root.insert(&components, file.is_dir(), file.size());
+    }
+    root.aggregate();
+
+    let mut entries = Vec::new();
+    flatten(&root, path, depth + 1, &mut entries);
+    Ok(entries)
+}
+
+fn flatten(node: &Node, parent: &Path, depth: usize, out: &mut Vec<TreeEntry>) {
+    let last = node.children.len().saturating_sub(1);
+    for (i, (name, child)) in node.children.iter().enumerate() {
+        let path = parent.join(name);
+        out.push(TreeEntry {
+            path: path.clone(),
+            depth,
+            connector: if i == last { "└──" } else { "├──" }.to_string(),
+            size: Some(child.size),
+            dirs: Some(child.children.values().filter(|c| c.is_dir).count() as u64),
+            files: Some(child.children.values().filter(|c| !c.is_dir).count() as u64),
+            is_directory: child.is_dir,
+            in_archive: true,
+            ..Default::default()
+        });
+        flatten(child, &path, depth + 1, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+    use zip::write::SimpleFileOptions;
+
+    #[test]
+    fn test_list_zip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bundle.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("docs/guide.md", options).unwrap();
+        zip.write_all(b"hello").unwrap();
+        zip.start_file("readme.txt", options).unwrap();
+        zip.write_all(b"hi").unwrap();
+        zip.finish().unwrap();
+
+        let entries = list_zip(&path, 1).unwrap();
+        let listed: Vec<_> = entries
+            .iter()
+            .map(|e| (e.path.strip_prefix(&path).unwrap().to_path_buf(), e.depth, e.size))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("docs".into(), 2, Some(5)),
+                ("docs/guide.md".into(), 3, Some(5)),
+                ("readme.txt".into(), 2, Some(2)),
+            ]
+        );
+        assert!(entries.iter().all(|e| e.in_archive));
+        assert!(entries[0].is_directory);
+        assert_eq!(entries[1].connector, "└──");
+        assert_eq!(entries[0].connector, "├──");
+    }
+}
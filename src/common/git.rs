@@ -0,0 +1,85 @@
+use anyhow::Context;
+use git2::{Delta, DiffOptions, Repository, Status, StatusOptions};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Canonical paths of the files added or modified since `reference`
+/// in the repository containing `root` (including uncommitted and untracked changes)
+pub fn changed_since(root: &Path, reference: &str) -> anyhow::Result<HashSet<PathBuf>> {
+    let repo = Repository::discover(root)
+        .with_context(|| format!("'{}' is not inside a git repository", root.display()))?;
+    let workdir = repo.workdir().context("--since-commit needs a repository with a work tree")?;
+    let workdir = workdir.canonicalize().unwrap_or_else(|_| workdir.to_path_buf());
+
+    let base = repo
+        .revparse_single(reference)
+        .and_then(|object| object.peel_to_tree())
+        .with_context(|| format!("Unknown git reference '{reference}'"))?;
+
+    let mut options = DiffOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+    let diff = repo.diff_tree_to_workdir_with_index(Some(&base), Some(&mut options))?;
+
+    Ok(diff
+        .deltas()
+        .filter(|delta| {
+            matches!(
+                delta.status(),
+                Delta::Added | Delta::Modified | Delta::Renamed | Delta::Copied | Delta::Untracked
+            )
+        })
+        .filter_map(|delta| delta.new_file().path().map(|path| workdir.join(path)))
+        .collect())
+}
+
+/// Working tree state of an entry, for `--git-status` (tracked files have none)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileStatus {
+    Untracked,
+    Ignored,
+}
+
+/// Untracked and ignored paths of the repository containing a scan root
+pub struct StatusMap {
+    root: PathBuf,
+    paths: HashMap<PathBuf, FileStatus>,
+}
+
+impl StatusMap {
+    /// Reads the status of the repository containing `root`, or `None` outside a repository
+    pub fn discover(root: &Path) -> Option<Self> {
+        let repo = Repository::discover(root).ok()?;
+        let workdir = repo.workdir()?;
+        let workdir = workdir.canonicalize().unwrap_or_else(|_| workdir.to_path_buf());
+        let canonical_root = root.canonicalize().ok()?;
+
+        let mut options = StatusOptions::new();
+        options.include_untracked(true).include_ignored(true);
+        let statuses = repo.statuses(Some(&mut options)).ok()?;
+
+        // Untracked and ignored directories are reported once, as `dir/`
+        let paths = statuses
+            .iter()
+            .filter_map(|entry| {
+                let status = if entry.status().contains(Status::IGNORED) {
+                    FileStatus::Ignored
+                } else if entry.status().contains(Status::WT_NEW) {
+                    FileStatus::Untracked
+                } else {
+                    return None;
+                };
+                let path = workdir.join(entry.path().ok()?);
+                let rel = path.strip_prefix(&canonical_root).ok()?;
+                Some((rel.to_path_buf(), status))
+            })
+            .collect();
+
+        Some(Self { root: root.to_path_buf(), paths })
+    }
+
+    /// Status of a scanned path; entries inside an untracked or ignored directory inherit it
+    pub fn get(&self, path: &Path) -> Option<FileStatus> {
+        let rel = path.strip_prefix(&self.root).ok()?;
+        rel.ancestors().find_map(|ancestor| self.paths.get(ancestor).copied())
+    }
+}
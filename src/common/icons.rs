@@ -1,6 +1,21 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-/// Returns Emoji icon for a given file path.
+/// Glyph family of the entry icons, selectable with `--icon-set`
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconSet {
+    /// Emoji, readable without a patched font
+    #[default]
+    Emoji,
+    /// Nerd Font glyphs, single-width and aligned (requires a Nerd Font)
+    #[value(name = "nerd")]
+    #[serde(rename = "nerd")]
+    NerdFont,
+}
+
+/// Returns the icon of `set` for a given file path.
 ///
 /// The selection logic  checks for file extensions.
 ///
@@ -8,19 +23,28 @@ use std::path::Path;
 ///
 /// * `path` - A reference to the `Path` of the file or directory.
 /// * `is_dir` - A boolean indicating if the `path` is a directory.
+/// * `set` - The icon family to pick from.
 ///
 /// # Returns
-/// * `String` - The Emoji icon.
-pub fn get_icon_for_path(path: &Path, is_dir: bool) -> String {
-    if is_dir {
-        return "📁".to_string(); // Cartella
-    }
-
+/// * `String` - The icon.
+pub fn get_icon_for_path(path: &Path, is_dir: bool, set: IconSet) -> String {
     // Estensione del file
     let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
 
+    match set {
+        IconSet::Emoji => emoji_icon(&ext, is_dir),
+        IconSet::NerdFont => nerd_icon(&ext, is_dir),
+    }
+    .to_string()
+}
+
+fn emoji_icon(ext: &str, is_dir: bool) -> &'static str {
+    if is_dir {
+        return "📁"; // Cartella
+    }
+
     // Icone basate sul tipo di file
-    let icon = match ext.as_str() {
+    match ext {
         // --- Linguaggi di programmazione ---
         "rs" => "🦀",                                     // Rust
         "py" => "🐍",                                     // Python
@@ -88,7 +112,83 @@ pub fn get_icon_for_path(path: &Path, is_dir: bool) -> String {
 
         // Default
         _ => "📄",
-    };
+    }
+}
 
-    icon.to_string()
+fn nerd_icon(ext: &str, is_dir: bool) -> &'static str {
+    if is_dir {
+        return "\u{f07b}"; // nf-fa-folder
+    }
+
+    match ext {
+        // --- Linguaggi di programmazione ---
+        "rs" => "\u{e7a8}",
+        "py" => "\u{e73c}",
+        "js" | "mjs" => "\u{e74e}",
+        "ts" | "tsx" => "\u{e628}",
+        "java" => "\u{e738}",
+        "cpp" | "cc" | "cxx" | "hpp" | "h" | "c" => "\u{e61d}",
+        "go" => "\u{e626}",
+        "php" => "\u{e73d}",
+        "rb" => "\u{e739}",
+        "swift" => "\u{e755}",
+        "kt" | "kts" => "\u{e634}",
+        "dart" => "\u{e798}",
+        "lua" => "\u{e620}",
+        "html" => "\u{e736}",
+        "css" | "scss" | "less" => "\u{e749}",
+        "sql" | "db" | "sqlite" | "db3" => "\u{f1c0}",
+
+        // --- Configurazioni e script ---
+        "json" | "jsonl" | "ndjson" => "\u{e60b}",
+        "toml" | "yaml" | "yml" | "ini" | "env" | "conf" | "cfg" => "\u{e615}",
+        "lock" => "\u{f023}",
+        "sh" | "bash" | "zsh" | "ps1" => "\u{f489}",
+        "dockerfile" => "\u{f308}",
+        "makefile" | "mk" => "\u{f0ad}",
+
+        // --- Documenti ---
+        "md" | "markdown" => "\u{f48a}",
+        "pdf" => "\u{f1c1}",
+        "doc" | "docx" => "\u{f1c2}",
+        "xls" | "xlsx" | "ods" | "csv" | "tsv" => "\u{f1c3}",
+        "ppt" | "pptx" | "odp" => "\u{f1c4}",
+        "txt" | "rtf" | "log" => "\u{f15c}",
+        "xml" => "\u{f121}",
+
+        // --- Archivi ---
+        "zip" | "gz" | "tar" | "rar" | "7z" | "bz2" => "\u{f410}",
+        "iso" => "\u{f0a0}",
+
+        // --- Media ---
+        "png" | "jpg" | "jpeg" | "gif" | "bmp" | "svg" | "ico" | "webp" | "psd" | "xcf" => {
+            "\u{f1c5}"
+        }
+        "mp3" | "wav" | "flac" | "ogg" | "m4a" => "\u{f001}",
+        "mp4" | "mkv" | "avi" | "mov" | "webm" => "\u{f03d}",
+        "srt" | "vtt" => "\u{f0e5}",
+
+        // --- Eseguibili e sistema ---
+        "exe" | "bin" | "app" | "msi" => "\u{f0e7}",
+        "dll" | "so" | "dylib" | "wasm" => "\u{f471}",
+        "bat" | "cmd" => "\u{f17a}",
+        "pem" | "crt" | "cer" | "key" => "\u{f084}",
+
+        // Default
+        _ => "\u{f15b}",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icon_sets() {
+        let rust = Path::new("main.rs");
+        assert_eq!(get_icon_for_path(rust, false, IconSet::Emoji), "🦀");
+        assert_eq!(get_icon_for_path(rust, false, IconSet::NerdFont), "\u{e7a8}");
+        assert_eq!(get_icon_for_path(Path::new("src"), true, IconSet::NerdFont), "\u{f07b}");
+        assert_eq!(get_icon_for_path(Path::new("a.unknown"), false, IconSet::NerdFont), "\u{f15b}");
+    }
 }
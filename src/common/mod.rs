@@ -1,3 +1,5 @@
+pub mod archive;
+pub mod git;
 pub mod icons;
 pub mod plugins;
 pub mod sort;
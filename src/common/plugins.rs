@@ -4,10 +4,12 @@ use std::sync::Mutex;
 use once_cell::sync::Lazy;
 
 type FilterFn = Box<dyn Fn(Box<dyn Any + Send>) -> Box<dyn Any + Send> + Send + Sync>;
+type FilterMap = HashMap<String, HashMap<TypeId, Vec<FilterFn>>>;
 
-static FILTERS: Lazy<Mutex<HashMap<String, HashMap<TypeId, Vec<FilterFn>>>>> =
-    Lazy::new(|| Mutex::new(HashMap::new()));
+static FILTERS: Lazy<Mutex<FilterMap>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
+/// Registers a filter for `hook`; only called from plugins, hence unused by the core.
+#[allow(dead_code)]
 pub fn add_filter<T>(
     hook: impl Into<String>,
     filter: impl Fn(T) -> T + Send + Sync + 'static,
@@ -28,19 +30,30 @@ pub fn add_filter<T>(
         }));
 }
 
+/// Removes every filter registered for `hook`.
+#[allow(dead_code)]
+pub fn remove_filters(hook: &str) {
+    FILTERS.lock().unwrap().remove(hook);
+}
+
+/// Whether any filter is registered for `hook`, so hot loops can skip the lookup.
+pub fn has_filters(hook: &str) -> bool {
+    FILTERS.lock().unwrap().get(hook).is_some_and(|hook_map| !hook_map.is_empty())
+}
+
 pub fn apply_filter<T>(hook: &str, value: T) -> T
 where
     T: Any + Send + 'static,
 {
     let filters = FILTERS.lock().unwrap();
-    let mut val: Box<dyn Any + Send> = Box::new(value);
+    let Some(filter_list) = filters.get(hook).and_then(|hook_map| hook_map.get(&TypeId::of::<T>()))
+    else {
+        return value;
+    };
 
-    if let Some(hook_map) = filters.get(hook) {
-        if let Some(filter_list) = hook_map.get(&TypeId::of::<T>()) {
-            for filter in filter_list {
-                val = filter(val);
-            }
-        }
+    let mut val: Box<dyn Any + Send> = Box::new(value);
+    for filter in filter_list {
+        val = filter(val);
     }
 
     *val.downcast::<T>().expect("Type mismatch in filter application")
@@ -82,4 +95,15 @@ mod tests {
 
         assert_eq!(c, "ciao");
     }
+
+    #[test]
+    fn test_remove_filters() {
+        add_filter("removed", |x: i32| x + 1);
+        assert!(has_filters("removed"));
+        assert_eq!(apply_filter("removed", 1), 2);
+
+        remove_filters("removed");
+        assert!(!has_filters("removed"));
+        assert_eq!(apply_filter("removed", 1), 1);
+    }
 }
@@ -4,30 +4,33 @@
 //! ensuring consistent behavior across all supported platforms (Windows, macOS, Linux).
 
 use ignore::DirEntry;
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fs::Metadata;
 use std::path::{Path, PathBuf};
+use std::sync::Once;
 use std::time::SystemTime;
 
 /// Defines the available sorting strategies.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum SortType {
+    #[default]
     Name,
     Size,
     Accessed,
     Created,
     Modified,
     Extension,
-}
-
-impl Default for SortType {
-    fn default() -> Self {
-        Self::Name
-    }
+    /// Number of direct children (files + dirs) among the walked entries, so after
+    /// the filters and within the depth limit; files count as 0
+    Children,
+    /// Keep the walk order (only the dirs/dotfiles-first grouping applies)
+    None,
 }
 
 /// Configuration options for sorting directory entries.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SortOptions {
     pub sort_type: SortType,
     pub directories_first: bool,
@@ -35,18 +38,29 @@ pub struct SortOptions {
     pub natural_sort: bool,
     pub reverse: bool,
     pub dotfiles_first: bool,
+    /// Time-sort directories by their most recently modified descendant
+    pub recursive_mtime: bool,
+    /// Criterion for directories only, overriding `sort_type`
+    pub dirs_sort_type: Option<SortType>,
+    /// Criterion for files only, overriding `sort_type`
+    pub files_sort_type: Option<SortType>,
 }
 
-impl Default for SortOptions {
-    fn default() -> Self {
-        Self {
-            sort_type: SortType::default(),
-            directories_first: false,
-            case_sensitive: false,
-            natural_sort: false,
-            reverse: false,
-            dotfiles_first: false,
-        }
+impl SortOptions {
+    /// Criterion applied to directories (`is_dir`) or files
+    fn sort_type_for(&self, is_dir: bool) -> SortType {
+        let split = if is_dir { self.dirs_sort_type } else { self.files_sort_type };
+        split.unwrap_or(self.sort_type)
+    }
+
+    /// Whether directories and files are ordered by different criteria
+    fn is_split(&self) -> bool {
+        self.sort_type_for(true) != self.sort_type_for(false)
+    }
+
+    /// Whether either directories or files are ordered by `sort_type`
+    pub fn sorts_by(&self, sort_type: SortType) -> bool {
+        self.sort_type_for(true) == sort_type || self.sort_type_for(false) == sort_type
     }
 }
 
@@ -56,24 +70,37 @@ struct EntryCache {
     is_dir: bool,
     is_dotfile: bool,
     size: u64,
+    children: usize,
     accessed: Option<SystemTime>,
     created: Option<SystemTime>,
     modified: Option<SystemTime>,
-    extension: Option<String>, 
+    extension: Option<String>,
     cached_name: String,
+    path: PathBuf,
 }
 
 impl EntryCache {
-    fn new(entry: &DirEntry, options: &SortOptions) -> Self {
+    fn new(
+        entry: &DirEntry,
+        options: &SortOptions,
+        mtimes: Option<&MtimeMap>,
+        children: Option<&ChildCounts>,
+        sizes: Option<&SizeMap>,
+        metadata: Option<&MetadataMap>,
+    ) -> Self {
         let file_name = entry.file_name();
         let file_name_str = file_name.to_string_lossy().to_string();
         let is_dotfile = file_name_str.starts_with('.');
         let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
 
-        let metadata = entry.metadata().ok();
+        let metadata = cached_metadata(entry, metadata);
         let (size, accessed, modified, created) = if let Some(m) = &metadata {
             (
-                if is_dir { 0 } else { m.len() },
+                if is_dir {
+                    sizes.and_then(|s| s.get(entry.path())).copied().unwrap_or(0)
+                } else {
+                    m.len()
+                },
                 m.accessed().ok(),
                 m.modified().ok(),
                 m.created().ok(),
@@ -82,7 +109,14 @@ impl EntryCache {
             (0, None, None, None)
         };
 
-        let extension = if options.sort_type == SortType::Extension {
+        let modified = match mtimes.and_then(|m| m.get(entry.path())) {
+            Some(&recursive) if is_dir => Some(recursive),
+            _ => modified,
+        };
+
+        let children = children.and_then(|c| c.get(entry.path())).copied().unwrap_or(0);
+
+        let extension = if options.sorts_by(SortType::Extension) {
             Path::new(&file_name_str).extension().and_then(|e| e.to_str()).map(|s| s.to_string())
         } else {
             None
@@ -92,27 +126,118 @@ impl EntryCache {
             is_dir,
             is_dotfile,
             size,
+            children,
             accessed,
             created,
             modified,
             extension,
             cached_name: file_name_str,
+            path: entry.path().to_path_buf(),
         }
     }
 }
 
+/// Metadata of each walked path, read once per scan and shared by sorting and
+/// tree building; without it every pass would `stat` each entry again.
+pub type MetadataMap = HashMap<PathBuf, Metadata>;
+
+/// Reads the metadata of every entry, one `stat` each.
+pub fn read_metadata(entries: &[DirEntry]) -> MetadataMap {
+    entries.iter().filter_map(|e| Some((e.path().to_path_buf(), e.metadata().ok()?))).collect()
+}
+
+/// Metadata of `entry` from `cache`, falling back to a fresh read for entries it lacks.
+pub fn cached_metadata<'a>(
+    entry: &DirEntry,
+    cache: Option<&'a MetadataMap>,
+) -> Option<Cow<'a, Metadata>> {
+    match cache.and_then(|cache| cache.get(entry.path())) {
+        Some(metadata) => Some(Cow::Borrowed(metadata)),
+        None => entry.metadata().ok().map(Cow::Owned),
+    }
+}
+
+/// Effective modification time of each path: the latest among itself and its descendants.
+pub type MtimeMap = HashMap<PathBuf, SystemTime>;
+
+/// Number of walked direct children of each directory, for `SortType::Children`.
+/// The same counts as the `dirs`/`files` that `Tree::build` gives each directory.
+pub type ChildCounts = HashMap<PathBuf, usize>;
+
+/// Total size of the files below each directory, for `--sort-dirs-by size`.
+pub type SizeMap = HashMap<PathBuf, u64>;
+
+/// Adds the size of every file to each of its ancestors (below the root).
+pub fn recursive_sizes(entries: &[DirEntry], metadata: Option<&MetadataMap>) -> SizeMap {
+    let mut sizes = SizeMap::new();
+    for entry in entries {
+        if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+            continue;
+        }
+        let Some(size) = cached_metadata(entry, metadata).map(|m| m.len()) else {
+            continue;
+        };
+        for path in entry.path().ancestors().skip(1).take(entry.depth().saturating_sub(1)) {
+            *sizes.entry(path.to_path_buf()).or_default() += size;
+        }
+    }
+    sizes
+}
+
+/// Computes the recursive modification time of every entry and its ancestors (below the root).
+pub fn recursive_mtimes(entries: &[DirEntry], metadata: Option<&MetadataMap>) -> MtimeMap {
+    let mut mtimes = MtimeMap::with_capacity(entries.len());
+    for entry in entries {
+        let Some(modified) = cached_metadata(entry, metadata).and_then(|m| m.modified().ok())
+        else {
+            continue;
+        };
+        // Stop as soon as an ancestor is already at least as recent
+        for path in entry.path().ancestors().take(entry.depth()) {
+            match mtimes.get_mut(path) {
+                Some(current) if *current >= modified => break,
+                Some(current) => *current = modified,
+                None => {
+                    mtimes.insert(path.to_path_buf(), modified);
+                }
+            }
+        }
+    }
+    mtimes
+}
+
 /// Sorts a slice of directory entries according to the given options.
 pub fn sort_entries(entries: &mut [DirEntry], options: &SortOptions) {
+    warn_if_time_unavailable(entries, options, None);
+    sort_entries_with(entries, options, None, None, None, None);
+}
+
+/// Sorts entries, taking directory modification times from `mtimes`, child
+/// counts from `children`, directory sizes from `sizes` and metadata from
+/// `metadata` when given.
+fn sort_entries_with(
+    entries: &mut [DirEntry],
+    options: &SortOptions,
+    mtimes: Option<&MtimeMap>,
+    children: Option<&ChildCounts>,
+    sizes: Option<&SizeMap>,
+    metadata: Option<&MetadataMap>,
+) {
     if entries.len() <= 1 {
         return;
     }
 
-    let cache: Vec<EntryCache> = entries.iter().map(|e| EntryCache::new(e, options)).collect();
+    let cache: Vec<EntryCache> = entries
+        .iter()
+        .map(|e| EntryCache::new(e, options, mtimes, children, sizes, metadata))
+        .collect();
     let mut indices: Vec<usize> = (0..entries.len()).collect();
 
     indices.sort_unstable_by(|&idx_a, &idx_b| {
         let cmp = compare_entries_cached(&cache[idx_a], &cache[idx_b], options);
-        if options.reverse { cmp.reverse() } else { cmp }
+        let cmp = if options.reverse { cmp.reverse() } else { cmp };
+        // Only reached with `SortType::None`: keep the walk order within a group
+        cmp.then(idx_a.cmp(&idx_b))
     });
 
     let mut visited = vec![false; entries.len()];
@@ -133,11 +258,17 @@ pub fn sort_entries(entries: &mut [DirEntry], options: &SortOptions) {
 }
 
 /// Sorts directory entries hierarchically, preserving tree structure.
-pub fn sort_entries_hierarchically(entries: &mut Vec<DirEntry>, options: &SortOptions) {
+/// Metadata comes from `metadata` when given (see [`read_metadata`]).
+pub fn sort_entries_hierarchically(
+    entries: &mut Vec<DirEntry>,
+    options: &SortOptions,
+    metadata: Option<&MetadataMap>,
+) {
     // Skip sorting if there's 0 or 1 entry.
     if entries.len() <= 1 {
         return;
     }
+    warn_if_time_unavailable(entries, options, metadata);
 
     let mut parent_to_children: HashMap<PathBuf, Vec<DirEntry>> =
         HashMap::with_capacity(entries.len() / 2);
@@ -145,23 +276,46 @@ pub fn sort_entries_hierarchically(entries: &mut Vec<DirEntry>, options: &SortOp
     // Group entries by their parent directory.
     for entry in entries.iter() {
         if let Some(parent) = entry.path().parent() {
-            parent_to_children
-                .entry(parent.to_path_buf())
-                .or_insert_with(Vec::new)
-                .push(entry.clone());
+            parent_to_children.entry(parent.to_path_buf()).or_default().push(entry.clone());
         }
     }
 
+    let mtimes = (options.recursive_mtime && options.sorts_by(SortType::Modified))
+        .then(|| recursive_mtimes(entries, metadata));
+    // `--sort size` keeps directories at 0; only an explicit directory criterion totals them
+    let sizes = (options.dirs_sort_type == Some(SortType::Size))
+        .then(|| recursive_sizes(entries, metadata));
+    let child_counts: Option<ChildCounts> = options.sorts_by(SortType::Children).then(|| {
+        parent_to_children
+            .iter()
+            .map(|(parent, children)| (parent.clone(), children.len()))
+            .collect()
+    });
+
     // Sort the children within each parent directory.
     for children in parent_to_children.values_mut() {
-        sort_entries(children, options);
+        sort_entries_with(
+            children,
+            options,
+            mtimes.as_ref(),
+            child_counts.as_ref(),
+            sizes.as_ref(),
+            metadata,
+        );
     }
 
     // Collect and sort all root-level entries (depth == 1).
     let mut root_entries: Vec<_> =
         entries.iter().filter(|entry| entry.depth() == 1).cloned().collect();
 
-    sort_entries(&mut root_entries, options);
+    sort_entries_with(
+        &mut root_entries,
+        options,
+        mtimes.as_ref(),
+        child_counts.as_ref(),
+        sizes.as_ref(),
+        metadata,
+    );
 
     // Rebuild the entries list in depth-first order starting from root nodes.
     let mut sorted_entries = Vec::with_capacity(entries.len());
@@ -189,6 +343,11 @@ fn collect_tree_recursive(
 }
 
 #[inline]
+/// Compares two entries; ties on the sort key fall back to the full path
+/// (bytewise), so the order never depends on the input order.
+/// `SortType::None` has no key and no tie-break: only the grouping orders.
+/// With different criteria for directories and files, directories come first
+/// so that each criterion only ever compares entries of its own kind.
 fn compare_entries_cached(
     cache_a: &EntryCache,
     cache_b: &EntryCache,
@@ -197,8 +356,28 @@ fn compare_entries_cached(
     if let Some(order) = compare_file_categories(cache_a, cache_b, options) {
         return order;
     }
+    if options.is_split() && cache_a.is_dir != cache_b.is_dir {
+        return cache_a.is_dir.cmp(&cache_b.is_dir).reverse();
+    }
+
+    let sort_type = options.sort_type_for(cache_a.is_dir);
+    if sort_type == SortType::None {
+        return Ordering::Equal;
+    }
+
+    compare_by_sort_key(cache_a, cache_b, sort_type, options).then_with(|| {
+        cache_a.path.as_os_str().as_encoded_bytes().cmp(cache_b.path.as_os_str().as_encoded_bytes())
+    })
+}
 
-    match options.sort_type {
+#[inline]
+fn compare_by_sort_key(
+    cache_a: &EntryCache,
+    cache_b: &EntryCache,
+    sort_type: SortType,
+    options: &SortOptions,
+) -> Ordering {
+    match sort_type {
         SortType::Name => compare_by_cached_name(
             &cache_a.cached_name,
             &cache_b.cached_name,
@@ -206,9 +385,11 @@ fn compare_entries_cached(
             options.case_sensitive,
         ),
         SortType::Size => cache_a.size.cmp(&cache_b.size),
+        SortType::Children => cache_a.children.cmp(&cache_b.children),
         SortType::Accessed => compare_by_time(&cache_a.accessed, &cache_b.accessed),
         SortType::Created => compare_by_time(&cache_a.created, &cache_b.created),
         SortType::Modified => compare_by_time(&cache_a.modified, &cache_b.modified),
+        SortType::None => Ordering::Equal,
         SortType::Extension => {
             let ext_a = cache_a.extension.as_deref().unwrap_or("");
             let ext_b = cache_b.extension.as_deref().unwrap_or("");
@@ -277,6 +458,67 @@ fn compare_by_cached_name(
     if natural { natord::compare(a_str, b_str) } else { a_str.cmp(b_str) }
 }
 
+/// Warns once on stderr when an `accessed`/`created` criterion has no times to
+/// sort by, e.g. creation times on filesystems that do not record them
+fn warn_if_time_unavailable(
+    entries: &[DirEntry],
+    options: &SortOptions,
+    metadata: Option<&MetadataMap>,
+) {
+    let missing = |sort_type: SortType, group: Option<bool>| {
+        let field: fn(&Metadata) -> std::io::Result<SystemTime> =
+            if sort_type == SortType::Accessed { Metadata::accessed } else { Metadata::created };
+        let times = entries
+            .iter()
+            .filter(|e| {
+                group.is_none_or(|is_dir| e.file_type().is_some_and(|ft| ft.is_dir()) == is_dir)
+            })
+            .map(|e| cached_metadata(e, metadata).and_then(|m| field(&m).ok()));
+        time_unavailable(times)
+    };
+    if let Some(warning) = time_warning(options, missing) {
+        static WARNED: Once = Once::new();
+        WARNED.call_once(|| eprintln!("{warning}"));
+    }
+}
+
+/// The warning for the first `accessed`/`created` criterion whose entries all
+/// lack that time, as told by `missing(sort_type, group)`. `group` is `None`
+/// for all entries, or whether the criterion covers directories
+/// (`--sort-dirs-by`) rather than files (`--sort-files-by`).
+fn time_warning(
+    options: &SortOptions,
+    missing: impl Fn(SortType, Option<bool>) -> bool,
+) -> Option<String> {
+    let groups: &[Option<bool>] =
+        if options.is_split() { &[Some(true), Some(false)] } else { &[None] };
+    groups.iter().find_map(|&group| {
+        let sort_type = options.sort_type_for(group.unwrap_or(false));
+        let sort = match sort_type {
+            SortType::Accessed => "accessed",
+            SortType::Created => "created",
+            _ => return None,
+        };
+        let flag = match group {
+            Some(true) if options.dirs_sort_type.is_some() => "--sort-dirs-by",
+            Some(false) if options.files_sort_type.is_some() => "--sort-files-by",
+            _ => "--sort",
+        };
+        missing(sort_type, group).then(|| {
+            format!(
+                "Warning: no entry has a {sort} time on this filesystem, so {flag} {sort} \
+                 falls back to path order; try {flag} modified"
+            )
+        })
+    })
+}
+
+/// Whether none of the (non-empty) `times` is known
+fn time_unavailable(times: impl IntoIterator<Item = Option<SystemTime>>) -> bool {
+    let mut times = times.into_iter().peekable();
+    times.peek().is_some() && times.all(|time| time.is_none())
+}
+
 #[inline]
 fn compare_by_time(time_a: &Option<SystemTime>, time_b: &Option<SystemTime>) -> Ordering {
     match (time_a, time_b) {
@@ -315,11 +557,187 @@ mod tests {
             .collect()
     }
 
+    #[test]
+    fn test_same_name_ties_broken_by_path() {
+        let dir = tempdir().unwrap();
+        for sub in ["b", "a", "c"] {
+            fs::create_dir(dir.path().join(sub)).unwrap();
+            File::create(dir.path().join(sub).join("same")).unwrap();
+        }
+        let mut entries: Vec<DirEntry> = WalkBuilder::new(dir.path())
+            .build()
+            .filter_map(Result::ok)
+            .filter(|e| e.depth() == 2)
+            .collect();
+
+        // Equal names are ordered by full path, whatever the input order
+        let expected: Vec<_> =
+            ["a", "b", "c"].iter().map(|s| dir.path().join(s).join("same")).collect();
+        for _ in 0..2 {
+            entries.reverse();
+            sort_entries(&mut entries, &SortOptions::default());
+            let paths: Vec<_> = entries.iter().map(|e| e.path().to_path_buf()).collect();
+            assert_eq!(paths, expected);
+        }
+    }
+
+    #[test]
+    fn test_recursive_mtime_sorts_deep_change_first() {
+        use std::time::Duration;
+
+        let dir = tempdir().unwrap();
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let newer = old + Duration::from_secs(1_000);
+        fs::create_dir_all(dir.path().join("deep/inner")).unwrap();
+        fs::create_dir(dir.path().join("shallow")).unwrap();
+        let touched = File::create(dir.path().join("deep/inner/recent.txt")).unwrap();
+        touched.set_modified(SystemTime::now()).unwrap();
+        for (path, time) in [("deep/inner", old), ("deep", old), ("shallow", newer)] {
+            File::open(dir.path().join(path)).unwrap().set_modified(time).unwrap();
+        }
+
+        let collect = |options: &SortOptions| {
+            let mut entries: Vec<DirEntry> =
+                WalkBuilder::new(dir.path()).build().filter_map(Result::ok).skip(1).collect();
+            sort_entries_hierarchically(&mut entries, options, None);
+            entries
+                .iter()
+                .filter(|e| e.depth() == 1)
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let options = SortOptions { sort_type: SortType::Modified, ..Default::default() };
+        assert_eq!(collect(&options), ["shallow", "deep"]);
+
+        let options = SortOptions { recursive_mtime: true, ..options };
+        assert_eq!(collect(&options), ["deep", "shallow"]);
+    }
+
+    #[test]
+    fn test_time_unavailable() {
+        assert!(time_unavailable([None, None]));
+        assert!(!time_unavailable([None, Some(SystemTime::UNIX_EPOCH)]));
+        // Nothing to sort is not a missing time field
+        assert!(!time_unavailable([]));
+    }
+
+    #[test]
+    fn test_time_warning_per_category() {
+        let created_missing = |sort_type: SortType, _| sort_type == SortType::Created;
+
+        let options = SortOptions { sort_type: SortType::Created, ..Default::default() };
+        assert!(time_warning(&options, created_missing).unwrap().contains("--sort created"));
+
+        let options =
+            SortOptions { files_sort_type: Some(SortType::Created), ..Default::default() };
+        assert!(
+            time_warning(&options, created_missing).unwrap().contains("--sort-files-by created")
+        );
+
+        let options =
+            SortOptions { dirs_sort_type: Some(SortType::Accessed), ..Default::default() };
+        assert_eq!(time_warning(&options, created_missing), None);
+        let accessed_missing =
+            |sort_type: SortType, group| sort_type == SortType::Accessed && group == Some(true);
+        assert!(
+            time_warning(&options, accessed_missing).unwrap().contains("--sort-dirs-by accessed")
+        );
+
+        // Only the category sorted by a time is checked
+        let options = SortOptions {
+            sort_type: SortType::Created,
+            dirs_sort_type: Some(SortType::Name),
+            ..Default::default()
+        };
+        assert_eq!(time_warning(&options, |_, group| group == Some(true)), None);
+        assert!(time_warning(&options, |_, group| group == Some(false)).is_some());
+    }
+
+    #[test]
+    fn test_sort_by_children_reversed() {
+        let dir = tempdir().unwrap();
+        for (sub, count) in [("few", 2), ("many", 5)] {
+            fs::create_dir(dir.path().join(sub)).unwrap();
+            for i in 0..count {
+                File::create(dir.path().join(sub).join(format!("f{i}"))).unwrap();
+            }
+        }
+        File::create(dir.path().join("file.txt")).unwrap();
+
+        let mut entries: Vec<DirEntry> =
+            WalkBuilder::new(dir.path()).build().filter_map(Result::ok).skip(1).collect();
+        let options =
+            SortOptions { sort_type: SortType::Children, reverse: true, ..Default::default() };
+        sort_entries_hierarchically(&mut entries, &options, None);
+
+        let roots: Vec<_> = entries
+            .iter()
+            .filter(|e| e.depth() == 1)
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(roots, ["many", "few", "file.txt"]);
+    }
+
+    #[test]
+    fn test_split_sort_dirs_by_size_files_by_name() {
+        let dir = tempdir().unwrap();
+        for (sub, size) in [("big", 300), ("small", 10), ("medium", 100)] {
+            fs::create_dir_all(dir.path().join(sub).join("nested")).unwrap();
+            fs::write(dir.path().join(sub).join("nested/data"), vec![0u8; size]).unwrap();
+        }
+        for (name, size) in [("c.txt", 1), ("a.txt", 50), ("b.txt", 500)] {
+            fs::write(dir.path().join(name), vec![0u8; size]).unwrap();
+        }
+
+        let mut entries: Vec<DirEntry> =
+            WalkBuilder::new(dir.path()).build().filter_map(Result::ok).skip(1).collect();
+        let options = SortOptions {
+            dirs_sort_type: Some(SortType::Size),
+            files_sort_type: Some(SortType::Name),
+            ..Default::default()
+        };
+        sort_entries_hierarchically(&mut entries, &options, None);
+
+        let roots: Vec<_> = entries
+            .iter()
+            .filter(|e| e.depth() == 1)
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        // Directories by total size (of nested files too), then files by name
+        assert_eq!(roots, ["small", "medium", "big", "a.txt", "b.txt", "c.txt"]);
+    }
+
+    #[test]
+    fn test_sort_none_dirs_first_keeps_walk_order() {
+        let mut entries = collect_entries_from_temp(&["b/", "z.txt", "a/", "m.txt", "c/", "a.txt"]);
+        // Any non name-sorted input order works: it must survive within each group
+        entries.reverse();
+        let names = |entries: &[DirEntry], dirs: bool| -> Vec<String> {
+            entries
+                .iter()
+                .filter(|e| e.file_type().is_some_and(|ft| ft.is_dir()) == dirs)
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        };
+        let expected = [names(&entries, true), names(&entries, false)].concat();
+
+        let options = SortOptions {
+            sort_type: SortType::None,
+            directories_first: true,
+            ..Default::default()
+        };
+        sort_entries(&mut entries, &options);
+
+        let sorted: Vec<_> =
+            entries.iter().map(|e| e.file_name().to_string_lossy().to_string()).collect();
+        assert_eq!(sorted, expected);
+    }
+
     #[test]
     fn test_sort_by_name_case_insensitive() {
         let mut entries = collect_entries_from_temp(&["banana", "Apple"]);
-        let mut options = SortOptions::default();
-        options.case_sensitive = false;
+        let options = SortOptions { case_sensitive: false, ..Default::default() };
 
         sort_entries(&mut entries, &options);
         let names: Vec<_> =
@@ -330,8 +748,7 @@ mod tests {
     #[test]
     fn test_sort_by_name_case_sensitive() {
         let mut entries = collect_entries_from_temp(&["banana", "Apple"]);
-        let mut options = SortOptions::default();
-        options.case_sensitive = true;
+        let options = SortOptions { case_sensitive: true, ..Default::default() };
 
         sort_entries(&mut entries, &options);
         let names: Vec<_> =
@@ -342,8 +759,7 @@ mod tests {
     #[test]
     fn test_sort_by_extension() {
         let mut entries = collect_entries_from_temp(&["a.t", "b.b", "c.T"]);
-        let mut options = SortOptions::default();
-        options.sort_type = SortType::Extension;
+        let options = SortOptions { sort_type: SortType::Extension, ..Default::default() };
 
         sort_entries(&mut entries, &options);
         let names: Vec<_> =
@@ -354,8 +770,7 @@ mod tests {
     #[test]
     fn test_sort_reverse() {
         let mut entries = collect_entries_from_temp(&["a", "b", "c"]);
-        let mut options = SortOptions::default();
-        options.reverse = true;
+        let options = SortOptions { reverse: true, ..Default::default() };
 
         sort_entries(&mut entries, &options);
         let names: Vec<_> =
@@ -367,8 +782,7 @@ mod tests {
     fn test_dotfiles_first() {
         let mut entries = collect_entries_from_temp(&[".hidden", "visible"]);
 
-        let mut options = SortOptions::default();
-        options.dotfiles_first = true;
+        let options = SortOptions { dotfiles_first: true, ..Default::default() };
 
         sort_entries(&mut entries, &options);
 
@@ -380,8 +794,7 @@ mod tests {
     #[test]
     fn test_directories_first() {
         let mut entries = collect_entries_from_temp(&["dir/", "file.txt"]);
-        let mut options = SortOptions::default();
-        options.directories_first = true;
+        let options = SortOptions { directories_first: true, ..Default::default() };
 
         sort_entries(&mut entries, &options);
         let names: Vec<_> =
@@ -1,14 +1,49 @@
-use crate::app::Args;
-use crate::common::plugins::apply_filter;
-use crate::common::{icons, sort};
+use crate::app::{Args, Column};
+use crate::common::plugins::{apply_filter, has_filters};
+use crate::common::{archive, git, icons, sort};
 use crate::utils::dir;
 use chrono::{DateTime, Utc};
-use ignore::WalkBuilder;
+use ignore::{WalkBuilder, WalkState};
 use indicatif::{ProgressBar, ProgressStyle};
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
-use std::collections::HashMap;
-use std::sync::mpsc::{channel, Receiver};
-use std::time::Duration;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// Quiet period required after the last filesystem event before refreshing
+pub const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How often a watch waiting for changes checks whether it was asked to stop
+const STOP_POLL: Duration = Duration::from_millis(100);
+
+/// Progress of a scan, for callers rendering their own progress UI
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// An entry passed the filters; `count` entries were scanned so far
+    Scanned { count: usize, path: PathBuf },
+    /// The walk is over and the tree is being sorted and aggregated
+    Computing,
+    /// The tree is ready
+    Done,
+}
+
+/// Spinner used by the CLI while scanning and computing
+fn new_spinner(message: &str) -> ProgressBar {
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.green} {msg}")
+            .unwrap()
+            .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ "),
+    );
+    spinner.set_message(message.to_string());
+    spinner.enable_steady_tick(Duration::from_millis(80));
+    spinner
+}
 
 /// Structure containing useful information for printing each entry
 #[derive(Debug, Clone)]
@@ -20,8 +55,25 @@ pub struct TreeEntry {
     pub dirs: Option<u64>,
     pub files: Option<u64>,
     pub permissions: Option<String>,
+    /// `user group ` names, with `--owner`
+    pub owner: Option<String>,
     pub icon: Option<String>,
+    pub modified: Option<std::time::SystemTime>,
     pub is_directory: bool,
+    pub is_executable: bool,
+    /// Dirs and files folded into this directory by `--max-size-per-dir`
+    pub collapsed: Option<(usize, usize)>,
+    /// Annotations set by plugins through the `"entry"` hook
+    pub tags: Vec<String>,
+    /// Virtual entry listed from inside an archive; it does not exist on disk
+    pub in_archive: bool,
+}
+
+impl TreeEntry {
+    /// Tags rendered as a bracketed suffix, e.g. `[todo, generated]`
+    pub fn tags_label(&self) -> Option<String> {
+        (!self.tags.is_empty()).then(|| format!("[{}]", self.tags.join(", ")))
+    }
 }
 
 impl Default for TreeEntry {
@@ -34,8 +86,14 @@ impl Default for TreeEntry {
             dirs: None,
             files: None,
             permissions: None,
+            owner: None,
             icon: None,
+            modified: None,
             is_directory: false,
+            is_executable: false,
+            collapsed: None,
+            tags: Vec::new(),
+            in_archive: false,
         }
     }
 }
@@ -45,21 +103,43 @@ impl Default for TreeEntry {
 pub struct Tree {
     pub entries: Vec<ignore::DirEntry>,
     pub tree_info: Vec<TreeEntry>,
+    /// Virtual contents of each listed archive, in tree order (`--into-archives`)
+    pub archive_entries: HashMap<PathBuf, Vec<TreeEntry>>,
+    /// The walk stopped early at `--max-total-size`
+    pub size_limit_reached: bool,
     depth_index: HashMap<usize, Vec<usize>>,
 }
 
 /// Watch mode handle for filesystem monitoring
 pub struct TreeWatcher {
-    _watcher: RecommendedWatcher,
+    _watcher: Box<dyn Watcher>,
     receiver: Receiver<Result<Event, notify::Error>>,
 }
 
+/// Returns true for events that may change the rendered tree.
+///
+/// Access events (open/close) are ignored: scanning the tree opens directories
+/// itself, so reacting to them would trigger an endless refresh loop.
+pub fn is_relevant_change(event: &Event) -> bool {
+    !event.kind.is_access()
+}
+
 impl TreeWatcher {
+    /// Wraps an already configured watcher and the channel it reports to
+    pub fn new(
+        watcher: impl Watcher + 'static,
+        receiver: Receiver<Result<Event, notify::Error>>,
+    ) -> Self {
+        TreeWatcher { _watcher: Box::new(watcher), receiver }
+    }
+
     /// Collect all pending changed paths (non-blocking)
     pub fn collect_changed_paths(&self) -> Vec<std::path::PathBuf> {
         let mut paths = Vec::new();
         while let Ok(result) = self.receiver.try_recv() {
-            if let Ok(event) = result {
+            if let Ok(event) = result
+                && is_relevant_change(&event)
+            {
                 paths.extend(event.paths);
             }
         }
@@ -68,20 +148,65 @@ impl TreeWatcher {
 
     /// Wait for the next filesystem change (blocking)
     pub fn wait_for_change(&self) -> bool {
-        self.receiver.recv().is_ok()
+        while let Ok(result) = self.receiver.recv() {
+            if result.is_ok_and(|event| is_relevant_change(&event)) {
+                return true;
+            }
+        }
+        false
     }
 
     /// Wait for changes with timeout
     pub fn wait_for_change_timeout(&self, timeout: Duration) -> bool {
-        self.receiver.recv_timeout(timeout).is_ok()
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.receiver.recv_timeout(remaining) {
+                Ok(Ok(event)) if is_relevant_change(&event) => return true,
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return false,
+            }
+        }
+    }
+
+    /// Wait for a change, then until the filesystem stays quiet for `debounce`.
+    ///
+    /// Returns false once the watcher is gone and no more changes can arrive.
+    pub fn wait_for_debounced_change(&self, debounce: Duration) -> bool {
+        if !self.wait_for_change() {
+            return false;
+        }
+        while self.wait_for_change_timeout(debounce) {}
+        true
     }
 
-    /// Drain all pending events (useful after rebuild)
-    pub fn drain_events(&self) {
-        while self.receiver.try_recv().is_ok() {}
+    /// Like [`Self::wait_for_debounced_change`], but also returns false once `stop`
+    /// is set, checked every [`STOP_POLL`] while no change arrives
+    pub fn wait_for_debounced_change_until(&self, debounce: Duration, stop: &AtomicBool) -> bool {
+        while !stop.load(Ordering::Relaxed) {
+            match self.receiver.recv_timeout(STOP_POLL) {
+                Ok(Ok(event)) if is_relevant_change(&event) => {
+                    while self.wait_for_change_timeout(debounce) {}
+                    return !stop.load(Ordering::Relaxed);
+                }
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return false,
+            }
+        }
+        false
     }
 }
 
+/// Whether an entry at `depth` under `parent` is the last of its directory,
+/// given the `(depth, parent)` of every entry after it in tree order
+fn is_last_child<'a>(
+    depth: usize,
+    parent: Option<&Path>,
+    mut later: impl Iterator<Item = (usize, Option<&'a Path>)>,
+) -> bool {
+    later.all(|(later_depth, later_parent)| later_depth != depth || later_parent != parent)
+}
+
 /// Helper function to check if a file passes the time filter
 fn file_passes_time_filter(entry: &ignore::DirEntry, args: &Args) -> bool {
     let Some(ref time_filter) = args.time else {
@@ -108,6 +233,13 @@ fn should_exclude(entry: &ignore::DirEntry, args: &Args) -> bool {
     !is_dir && args.is_excluded(entry.path())
 }
 
+/// Whether `entry` is the file at the canonical path `path`
+#[inline]
+fn is_same_file(entry: &ignore::DirEntry, path: &Path) -> bool {
+    Some(entry.file_name()) == path.file_name()
+        && entry.path().canonicalize().is_ok_and(|p| p == path)
+}
+
 impl Tree {
     /// Prune directories that have no file descendants (used after time filtering)
     fn prune_empty_dirs(mut tree: Tree) -> Tree {
@@ -149,15 +281,74 @@ impl Tree {
         // Rebuild depth_index with known capacity
         let mut depth_index: HashMap<usize, Vec<usize>> = HashMap::new();
         for (new_i, info) in tree.tree_info.iter().enumerate() {
-            depth_index.entry(info.depth).or_insert_with(Vec::new).push(new_i);
+            depth_index.entry(info.depth).or_default().push(new_i);
         }
 
         tree.depth_index = depth_index;
         tree
     }
 
-    /// Builds the tree from DirEntry and Args
-    fn build(entries: Vec<ignore::DirEntry>, args: &Args) -> Self {
+    /// Folds the subtree of every directory larger than `max_size`; the outermost one wins.
+    /// Sizes are already aggregated and the folded counts are kept, so totals are unaffected.
+    fn collapse_large_dirs(mut tree: Tree, max_size: u64) -> Tree {
+        let mut collapsed_dir: Option<usize> = None;
+        let mut keep = Vec::with_capacity(tree.tree_info.len());
+
+        for i in 0..tree.tree_info.len() {
+            if let Some(dir_idx) = collapsed_dir {
+                let is_directory = tree.tree_info[i].is_directory;
+                if tree.tree_info[i].path.starts_with(&tree.tree_info[dir_idx].path) {
+                    if let Some((dirs, files)) = &mut tree.tree_info[dir_idx].collapsed {
+                        *if is_directory { dirs } else { files } += 1;
+                    }
+                    keep.push(false);
+                    continue;
+                }
+                collapsed_dir = None;
+            }
+            let info = &mut tree.tree_info[i];
+            if info.is_directory && info.size.unwrap_or(0) > max_size {
+                info.collapsed = Some((0, 0));
+                collapsed_dir = Some(i);
+            }
+            keep.push(true);
+        }
+
+        let mut keep_iter = keep.iter();
+        tree.entries.retain(|_| *keep_iter.next().unwrap());
+        let mut keep_iter = keep.iter();
+        tree.tree_info.retain(|_| *keep_iter.next().unwrap());
+
+        tree.depth_index.clear();
+        for (i, info) in tree.tree_info.iter().enumerate() {
+            tree.depth_index.entry(info.depth).or_default().push(i);
+        }
+        tree
+    }
+
+    /// Walks with `threads` workers (0 picks a count automatically); entries
+    /// come back sorted by path so the output does not depend on thread timing.
+    /// Filtering, sorting and aggregation stay sequential.
+    fn walk_parallel(mut builder: WalkBuilder, threads: usize) -> Vec<ignore::DirEntry> {
+        builder.threads(threads);
+        let found = Mutex::new(Vec::new());
+        builder.build_parallel().run(|| {
+            Box::new(|result| {
+                if let Ok(entry) = result {
+                    found.lock().unwrap().push(entry);
+                }
+                WalkState::Continue
+            })
+        });
+
+        // Component-wise path order keeps every directory ahead of its contents
+        let mut entries = found.into_inner().unwrap();
+        entries.sort_by(|a, b| a.path().cmp(b.path()));
+        entries
+    }
+
+    /// Builds the tree from DirEntry and Args, taking file metadata from `metadata`
+    fn build(entries: Vec<ignore::DirEntry>, args: &Args, metadata: &sort::MetadataMap) -> Self {
         // Pre-allocate with capacity
         let capacity = entries.len() + 1;
         let mut infos: HashMap<std::path::PathBuf, TreeEntry> = HashMap::with_capacity(capacity);
@@ -169,14 +360,18 @@ impl Tree {
         // First pass: gather info about files and directories
         for entry in &entries {
             let path = entry.path();
-            let is_dir = entry.file_type().map_or(false, |ft| ft.is_dir());
+            let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
 
-            let info = infos.entry(path.to_path_buf()).or_insert_with(TreeEntry::default);
+            let info = infos.entry(path.to_path_buf()).or_default();
 
             info.is_directory = is_dir;
 
             if !is_dir {
-                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                // Read once here so styling does not have to stat every file again
+                let metadata = sort::cached_metadata(entry, Some(metadata));
+                let size = metadata.as_ref().map_or(0, |m| dir::file_size(m, args.disk_usage));
+                info.is_executable =
+                    metadata.is_some_and(|m| m.is_file() && dir::is_executable(path, &m));
                 info.files = Some(1);
                 info.size = Some(size);
                 info.dirs = Some(0);
@@ -209,6 +404,7 @@ impl Tree {
         // Filter entries according to args.files_only and args.files
         let max_files = args.files;
         let files_only = args.files_only;
+        let only_extensions = args.only_extensions();
         let mut filtered_entries = Vec::with_capacity(entries.len());
         let mut files_count_in_dir: HashMap<std::path::PathBuf, usize> = HashMap::new();
 
@@ -220,23 +416,30 @@ impl Tree {
                 continue;
             }
 
-            if !is_dir {
-                if let Some(max) = max_files {
-                    let parent = path.parent().unwrap_or(path);
-                    let count = files_count_in_dir.entry(parent.to_path_buf()).or_insert(0);
-
-                    if *count >= max {
-                        if let Some(parent_info) = infos.get_mut(parent) {
-                            parent_info.files = Some(parent_info.files.unwrap_or(0) + 1);
-                            parent_info.size = Some(
-                                parent_info.size.unwrap_or(0)
-                                    + entry.metadata().map(|m| m.len()).unwrap_or(0),
-                            );
-                        }
-                        continue;
-                    }
-                    *count += 1;
+            // Size filters only hide files; their size still counts toward the parents
+            if !is_dir && !args.size_allowed(infos.get(path).and_then(|i| i.size).unwrap_or(0)) {
+                continue;
+            }
+
+            // Same for `--only-ext`; directories left without files are pruned afterwards
+            if !is_dir
+                && let Some(extensions) = &only_extensions
+                && !path
+                    .extension()
+                    .is_some_and(|ext| extensions.contains(&ext.to_string_lossy().to_lowercase()))
+            {
+                continue;
+            }
+
+            if !is_dir && let Some(max) = max_files {
+                let parent = path.parent().unwrap_or(path);
+                let count = files_count_in_dir.entry(parent.to_path_buf()).or_insert(0);
+
+                // Hidden files were already counted into their parents by the propagation
+                if *count >= max {
+                    continue;
                 }
+                *count += 1;
             }
 
             filtered_entries.push(entry);
@@ -246,39 +449,58 @@ impl Tree {
         let len = filtered_entries.len();
         let mut tree_info = Vec::with_capacity(len);
         let mut depth_index: HashMap<usize, Vec<usize>> = HashMap::new();
+        let entry_hook = has_filters("entry");
 
-        let show_permissions = args.permissions;
-        let show_icons = args.icons;
+        let show_permissions = args.permissions || args.has_column(Column::Perms);
+        let show_modified = args.has_column(Column::Mtime);
+        let mut owners =
+            (args.owner || args.has_column(Column::Owner)).then(dir::OwnerNames::default);
+        let recursive_mtimes = (show_modified && args.recursive_mtime)
+            .then(|| sort::recursive_mtimes(&filtered_entries, Some(metadata)));
 
         for (i, entry) in filtered_entries.iter().enumerate() {
             let path = entry.path();
             let original_depth = entry.depth();
             let depth = if files_only { 1 } else { original_depth };
 
-            // Optimized is_last check
-            let is_last = filtered_entries[i + 1..].iter().all(|e| {
-                let e_depth = if files_only { 1 } else { e.depth() };
-                e_depth != depth || e.path().parent() != path.parent()
-            });
+            let later = filtered_entries[i + 1..]
+                .iter()
+                .map(|e| (if files_only { 1 } else { e.depth() }, e.path().parent()));
+            let is_last = is_last_child(depth, path.parent(), later);
 
             let connector = if is_last { "└──" } else { "├──" };
             let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
 
             let permissions = if show_permissions {
-                Some(dir::get_permission(entry.metadata().ok()))
+                Some(dir::get_permission(
+                    sort::cached_metadata(entry, Some(metadata)).map(Cow::into_owned),
+                    args.perms_style(),
+                ))
             } else {
                 None
             };
 
-            let icon = if show_icons {
-                Some(format!("{} ", icons::get_icon_for_path(path, is_dir)))
+            let owner = owners.as_mut().and_then(|owners| {
+                sort::cached_metadata(entry, Some(metadata)).map(|m| owners.owner(&m))
+            });
+
+            let icon = if args.shows_icon(is_dir) {
+                Some(format!("{} ", icons::get_icon_for_path(path, is_dir, args.icon_set)))
             } else {
                 None
             };
 
+            let modified = match &recursive_mtimes {
+                Some(mtimes) => mtimes.get(path).copied(),
+                None if show_modified => {
+                    sort::cached_metadata(entry, Some(metadata)).and_then(|m| m.modified().ok())
+                }
+                None => None,
+            };
+
             let info = infos.get(path).cloned().unwrap_or_default();
 
-            tree_info.push(TreeEntry {
+            let tree_entry = TreeEntry {
                 path: path.to_path_buf(),
                 depth,
                 connector: connector.to_string(),
@@ -286,14 +508,29 @@ impl Tree {
                 dirs: info.dirs,
                 files: info.files,
                 permissions,
+                owner,
                 icon,
+                modified,
                 is_directory: is_dir,
-            });
+                is_executable: info.is_executable,
+                collapsed: None,
+                tags: Vec::new(),
+                in_archive: false,
+            };
 
-            depth_index.entry(depth).or_insert_with(Vec::new).push(i);
+            // Plugins can annotate or adjust every entry
+            tree_info.push(if entry_hook { apply_filter("entry", tree_entry) } else { tree_entry });
+
+            depth_index.entry(depth).or_default().push(i);
         }
 
-        Tree { entries: filtered_entries, tree_info, depth_index }
+        Tree {
+            entries: filtered_entries,
+            tree_info,
+            archive_entries: HashMap::new(),
+            size_limit_reached: false,
+            depth_index,
+        }
     }
 
     /// Creates a filesystem watcher for the given path
@@ -315,40 +552,124 @@ impl Tree {
 
         watcher.watch(&args.path, watch_mode)?;
 
-        Ok(TreeWatcher { _watcher: watcher, receiver: rx })
+        Ok(TreeWatcher::new(watcher, rx))
     }
 
     /// Prepares the tree from Args (scans files and directories)
     pub fn prepare(args: &Args, show_progress: bool) -> anyhow::Result<Self> {
+        Self::prepare_with(args, show_progress, None)
+    }
+
+    /// Like [`Tree::prepare`], scanning down to `max_depth_override` (same meaning as
+    /// `--level`, negative included) instead of `args.level` when given
+    pub fn prepare_with(
+        args: &Args,
+        show_progress: bool,
+        max_depth_override: Option<isize>,
+    ) -> anyhow::Result<Self> {
+        let level = max_depth_override.or(args.level);
+        if !show_progress {
+            return Self::prepare_with_progress(args, level, |_| {});
+        }
+
+        // The CLI spinner is just one consumer of the progress events
+        let mut spinner = new_spinner("Scanning:");
+        Self::prepare_with_progress(args, level, |event| match event {
+            ProgressEvent::Scanned { path, .. } => {
+                spinner.set_message(format!("Scanning: {}", path.display()));
+            }
+            ProgressEvent::Computing => {
+                spinner.finish_with_message("Completed ✅");
+                spinner = new_spinner("Computing:");
+            }
+            ProgressEvent::Done => {
+                spinner.finish_with_message("Completed ✅");
+                println!("\n");
+            }
+        })
+    }
+
+    /// Prepares the tree down to `level` (see `--level`), reporting progress to
+    /// `on_progress` instead of drawing a spinner
+    pub fn prepare_with_progress(
+        args: &Args,
+        level: Option<isize>,
+        mut on_progress: impl FnMut(ProgressEvent),
+    ) -> anyhow::Result<Self> {
+        // The parallel walk is put back in path order, which only sorted output hides
+        if args.threads.is_some() {
+            if args.max_total_size.is_some() {
+                anyhow::bail!("--threads cannot be combined with --max-total-size");
+            }
+            if args.to_sort_options().sorts_by(sort::SortType::None) {
+                anyhow::bail!("--threads cannot be combined with --sort none");
+            }
+        }
+
         let mut builder = WalkBuilder::new(&args.path);
         builder.hidden(!args.all).git_ignore(args.gitignore);
-        builder.max_depth(args.level);
-
-        let spinner = if show_progress {
-            let spinner = ProgressBar::new_spinner();
-            spinner.set_style(
-                ProgressStyle::default_spinner()
-                    .template("{spinner:.green} {msg}")
-                    .unwrap()
-                    .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ "),
-            );
-            spinner.set_message("Scanning:".to_string());
-            spinner.enable_steady_tick(Duration::from_millis(80));
-            spinner
-        } else {
-            ProgressBar::hidden()
-        };
+        // Negative levels need the full walk to know the deepest level
+        builder.max_depth(level.and_then(|level| usize::try_from(level).ok()));
+        builder.follow_links(args.follow_symlinks);
+        let root = args.path.canonicalize().unwrap_or_else(|_| args.path.clone());
+        // Links back to an ancestor are reported as loops by the walker and dropped. Links to
+        // another directory of the tree are listed as leaves: their target is listed anyway.
+        let link_leaves = args.follow_symlinks.then(|| Mutex::new(HashSet::new()));
+        let no_dotfiles = args.no_dotfiles;
+        if no_dotfiles || link_leaves.is_some() {
+            let root = root.clone();
+            builder.filter_entry(move |e| {
+                // Prunes whole dot-directories; other platform-hidden entries follow --all
+                if no_dotfiles && e.depth() > 0 && e.file_name().to_string_lossy().starts_with('.')
+                {
+                    return false;
+                }
+                let Some(link_leaves) = &link_leaves else { return true };
+                if e.path().parent().is_some_and(|p| link_leaves.lock().unwrap().contains(p)) {
+                    return false;
+                }
+                if e.depth() > 0
+                    && e.path_is_symlink()
+                    && e.file_type().is_some_and(|ft| ft.is_dir())
+                    && e.path().canonicalize().is_ok_and(|real| real.starts_with(&root))
+                {
+                    link_leaves.lock().unwrap().insert(e.path().to_path_buf());
+                }
+                true
+            });
+        }
+        for ignore_file in &args.ignore_file {
+            if let Some(err) = builder.add_ignore(ignore_file) {
+                anyhow::bail!("Invalid ignore file {}: {}", ignore_file.display(), err);
+            }
+        }
 
         let mut entries = Vec::new();
         let has_time_filter = args.time.is_some();
         let has_exclude_filter = args.exclude.is_some();
         let dirs_only = args.dirs_only;
+        let hidden_config = if args.hide_config { args.loaded_config.as_deref() } else { None };
+        let changed_files = match &args.since_commit {
+            Some(reference) => Some(git::changed_since(&args.path, reference)?),
+            None => None,
+        };
+        let mut total_size = 0u64;
+        let mut size_limit_reached = false;
+
+        let walked: Box<dyn Iterator<Item = ignore::DirEntry>> = match args.threads {
+            Some(threads) => Box::new(Self::walk_parallel(builder, threads).into_iter()),
+            None => Box::new(builder.build().filter_map(Result::ok)),
+        };
 
-        for entry in builder.build().filter_map(Result::ok) {
+        for entry in walked {
             if entry.depth() == 0 {
                 continue;
             }
 
+            if args.ignore_symlinks && entry.path_is_symlink() {
+                continue;
+            }
+
             let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
 
             // Apply dirs_only filter
@@ -361,53 +682,104 @@ impl Tree {
                 continue;
             }
 
+            // Hide the active config file
+            if let Some(config) = hidden_config
+                && !is_dir
+                && is_same_file(&entry, config)
+            {
+                continue;
+            }
+
             // Apply time filter only to files (dirs added unconditionally, pruned later)
             if has_time_filter && !is_dir && !file_passes_time_filter(&entry, args) {
                 continue;
             }
 
-            if show_progress {
-                spinner.set_message(format!("Scanning: {}", entry.path().display()));
+            // Apply git filter only to files (dirs pruned later, like the time filter)
+            if let Some(changed) = &changed_files
+                && !is_dir
+                && !entry
+                    .path()
+                    .strip_prefix(&args.path)
+                    .is_ok_and(|rel| changed.contains(&root.join(rel)))
+            {
+                continue;
+            }
+
+            // Stop before the file that would push the listed total past `--max-total-size`
+            if let Some(limit) = args.max_total_size
+                && !is_dir
+            {
+                total_size +=
+                    entry.metadata().map(|m| dir::file_size(&m, args.disk_usage)).unwrap_or(0);
+                if total_size > limit.0 {
+                    size_limit_reached = true;
+                    break;
+                }
             }
+
+            on_progress(ProgressEvent::Scanned {
+                count: entries.len() + 1,
+                path: entry.path().to_path_buf(),
+            });
             entries.push(entry);
         }
 
-        if show_progress {
-            spinner.finish_with_message("Completed ✅");
-        }
+        on_progress(ProgressEvent::Computing);
 
-        let spinner = if show_progress {
-            let spinner = ProgressBar::new_spinner();
-            spinner.set_style(
-                ProgressStyle::default_spinner()
-                    .template("{spinner:.green} {msg}")
-                    .unwrap()
-                    .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ "),
-            );
-            spinner.set_message("Computing:".to_string());
-            spinner.enable_steady_tick(Duration::from_millis(80));
-            spinner
-        } else {
-            ProgressBar::hidden()
-        };
+        // One `stat` per entry, shared by the sort and every pass of `build`
+        let metadata = sort::read_metadata(&entries);
 
         if args.files_only {
             sort::sort_entries(&mut entries, &args.to_sort_options())
         } else {
-            sort::sort_entries_hierarchically(&mut entries, &args.to_sort_options());
+            sort::sort_entries_hierarchically(
+                &mut entries,
+                &args.to_sort_options(),
+                Some(&metadata),
+            );
         }
 
-        let tree = Self::build(entries, args);
+        // A negative level keeps the deepest levels only: depths from `max + level + 1` down
+        // (after sorting, which rebuilds the order from the top level)
+        if let Some(level) = level.filter(|level| *level < 0) {
+            let max_depth = entries.iter().map(|e| e.depth()).max().unwrap_or(0);
+            let min_depth = max_depth.saturating_sub(level.unsigned_abs() - 1).max(1);
+            entries.retain(|e| e.depth() >= min_depth);
+        }
 
-        // Prune empty directories if time filter or exclude filter is active
-        let tree =
-            if has_time_filter || has_exclude_filter { Self::prune_empty_dirs(tree) } else { tree };
+        let mut tree = Self::build(entries, args, &metadata);
+        tree.size_limit_reached = size_limit_reached;
 
-        if show_progress {
-            spinner.finish_with_message("Completed ✅");
-            println!("\n");
+        // Prune empty directories if a time, exclude, extension or git filter is active
+        let tree = if has_time_filter
+            || has_exclude_filter
+            || args.only_ext.is_some()
+            || changed_files.is_some()
+        {
+            Self::prune_empty_dirs(tree)
+        } else {
+            tree
+        };
+
+        let mut tree = match args.max_size_per_dir {
+            Some(max) if !args.files_only => Self::collapse_large_dirs(tree, max.0),
+            _ => tree,
+        };
+
+        if args.into_archives {
+            for info in tree.tree_info.iter().filter(|i| !i.is_directory) {
+                // Unreadable or corrupt archives are listed as plain files
+                if archive::is_archive(&info.path)
+                    && let Ok(contents) = archive::list_zip(&info.path, info.depth)
+                {
+                    tree.archive_entries.insert(info.path.clone(), contents);
+                }
+            }
         }
 
+        on_progress(ProgressEvent::Done);
+
         Ok(apply_filter("tree_entries", tree))
     }
 
@@ -423,6 +795,30 @@ impl Tree {
         Ok((tree, watcher))
     }
 
+    /// Recursive directory count, file count and size of the whole tree, collapsed subtrees included
+    pub fn totals(&self) -> (usize, usize, u64) {
+        let (mut dir_count, mut file_count) = (0, 0);
+        for info in &self.tree_info {
+            if info.is_directory {
+                dir_count += 1;
+            } else {
+                file_count += 1;
+            }
+            if let Some((dirs, files)) = info.collapsed {
+                dir_count += dirs;
+                file_count += files;
+            }
+        }
+        let size = self.entries_at_depth(1).iter().map(|(_, info)| info.size.unwrap_or(0)).sum();
+
+        (dir_count, file_count, size)
+    }
+
+    /// Entries matching `predicate`, in tree order, without walking the filesystem again
+    pub fn find(&self, predicate: impl Fn(&TreeEntry) -> bool) -> Vec<&TreeEntry> {
+        self.tree_info.iter().filter(|info| predicate(info)).collect()
+    }
+
     /// Returns all entries at a given depth along with their info
     pub fn entries_at_depth(&self, depth: usize) -> Vec<(&ignore::DirEntry, &TreeEntry)> {
         self.depth_index
@@ -433,3 +829,206 @@ impl Tree {
             .unwrap_or_default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::plugins::{add_filter, remove_filters};
+    use clap::Parser;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_progress_events() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::File::create(dir.path().join("a.txt")).unwrap();
+        fs::File::create(dir.path().join("sub/b.txt")).unwrap();
+        let args = Args::parse_from(["wisu".as_ref(), dir.path().as_os_str()]);
+
+        let mut events = Vec::new();
+        let tree =
+            Tree::prepare_with_progress(&args, args.level, |event| events.push(event)).unwrap();
+
+        let scanned: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                ProgressEvent::Scanned { count, path } => Some((*count, path.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(scanned.len(), tree.entries.len());
+        assert_eq!(scanned.iter().map(|(count, _)| *count).collect::<Vec<_>>(), [1, 2, 3]);
+        for entry in &tree.entries {
+            assert!(scanned.iter().any(|(_, path)| path == entry.path()));
+        }
+        assert_eq!(events[events.len() - 2..], [ProgressEvent::Computing, ProgressEvent::Done]);
+    }
+
+    #[test]
+    fn test_prepare_with_depth_override() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        fs::File::create(dir.path().join("a/b/deep.txt")).unwrap();
+        let args = Args::parse_from([
+            "wisu".as_ref(),
+            dir.path().as_os_str(),
+            "-L".as_ref(),
+            "1".as_ref(),
+        ]);
+
+        let max_depth = |tree: &Tree| tree.tree_info.iter().map(|e| e.depth).max();
+        assert_eq!(max_depth(&Tree::prepare(&args, false).unwrap()), Some(1));
+        assert_eq!(max_depth(&Tree::prepare_with(&args, false, Some(3)).unwrap()), Some(3));
+        assert_eq!(max_depth(&Tree::prepare_with(&args, false, Some(2)).unwrap()), Some(2));
+        // Args itself is left untouched
+        assert_eq!(args.level, Some(1));
+    }
+
+    #[test]
+    fn test_files_cap_counts_hidden_files_once() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("many")).unwrap();
+        for i in 1..=10 {
+            fs::write(dir.path().join(format!("many/f{i:02}")), "x".repeat(i)).unwrap();
+        }
+        let args = Args::parse_from([
+            "wisu".as_ref(),
+            dir.path().as_os_str(),
+            "--files".as_ref(),
+            "3".as_ref(),
+        ]);
+
+        let tree = Tree::prepare(&args, false).unwrap();
+        let many = tree.tree_info.iter().find(|e| e.path.ends_with("many")).unwrap();
+        assert_eq!(many.files, Some(10));
+        assert_eq!(many.size, Some((1..=10).sum()));
+        assert_eq!(tree.tree_info.iter().filter(|e| !e.is_directory).count(), 3);
+    }
+
+    #[test]
+    fn test_is_last_child() {
+        let entries =
+            [(1, "a"), (2, "a/x"), (3, "a/x/deep"), (2, "a/y"), (1, "b"), (2, "b/z"), (1, "c")];
+        let last: Vec<bool> = (0..entries.len())
+            .map(|i| {
+                let (depth, path) = entries[i];
+                let later = entries[i + 1..].iter().map(|&(d, p)| (d, Path::new(p).parent()));
+                is_last_child(depth, Path::new(path).parent(), later)
+            })
+            .collect();
+        assert_eq!(last, [false, false, true, true, false, true, true]);
+
+        // Same depth under another parent does not count as a sibling
+        let later = [(2, Some(Path::new("b")))].into_iter();
+        assert!(is_last_child(2, Some(Path::new("a")), later));
+        assert!(is_last_child(1, Some(Path::new("")), std::iter::empty()));
+    }
+
+    #[test]
+    fn test_build_with_metadata_cache_matches_fresh_reads() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        for (name, len) in [("a.txt", 3), ("sub/b.rs", 10), ("sub/c.md", 7)] {
+            fs::write(dir.path().join(name), "x".repeat(len)).unwrap();
+        }
+        let args = Args::parse_from([
+            "wisu".as_ref(),
+            dir.path().as_os_str(),
+            "-p".as_ref(),
+            "-F".as_ref(),
+            "1".as_ref(),
+            "--columns".as_ref(),
+            "name,size,mtime".as_ref(),
+        ]);
+        let entries: Vec<_> =
+            WalkBuilder::new(dir.path()).build().filter_map(Result::ok).skip(1).collect();
+
+        // An empty cache makes every pass read the metadata itself
+        let cached = Tree::build(entries.clone(), &args, &sort::read_metadata(&entries));
+        let fresh = Tree::build(entries, &args, &sort::MetadataMap::new());
+        assert_eq!(format!("{:?}", cached.tree_info), format!("{:?}", fresh.tree_info));
+        assert!(cached.tree_info.iter().all(|e| e.permissions.is_some() && e.modified.is_some()));
+    }
+
+    #[test]
+    fn test_parallel_walk_matches_sequential() {
+        let dir = tempdir().unwrap();
+        for i in 0..20 {
+            let sub = dir.path().join(format!("dir{i}/nested"));
+            fs::create_dir_all(&sub).unwrap();
+            fs::File::create(sub.join("file.txt")).unwrap();
+            fs::File::create(dir.path().join(format!("dir{i}/top{i}.rs"))).unwrap();
+        }
+        let root = dir.path().as_os_str();
+        let sequential = Args::parse_from(["wisu".as_ref(), root]);
+        let parallel =
+            Args::parse_from(["wisu".as_ref(), root, "--threads".as_ref(), "4".as_ref()]);
+
+        let paths = |args: &Args| -> Vec<PathBuf> {
+            Tree::prepare(args, false).unwrap().tree_info.into_iter().map(|e| e.path).collect()
+        };
+        let expected = paths(&sequential);
+        assert_eq!(expected.len(), 80);
+        assert_eq!(paths(&parallel), expected);
+
+        let with = |extra: &[&str], threads: bool| {
+            let threads = if threads { &["--threads", "4"][..] } else { &[] };
+            let extra = extra.iter().chain(threads).map(|arg| arg.as_ref());
+            Args::parse_from(["wisu".as_ref(), root].into_iter().chain(extra))
+        };
+        for sort in [&["--sort", "size"][..], &["--sort", "extension", "--dirs-first"], &["-r"]] {
+            assert_eq!(paths(&with(sort, true)), paths(&with(sort, false)), "{sort:?}");
+        }
+
+        // Walk order and an early stop cannot be reproduced by a parallel walk
+        for extra in [&["--max-total-size", "1K"][..], &["--sort", "none"]] {
+            assert!(Tree::prepare(&with(extra, true), false).is_err(), "{extra:?}");
+        }
+    }
+
+    #[test]
+    fn test_find_by_size() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("small.txt"), "x").unwrap();
+        fs::write(dir.path().join("sub/big.bin"), vec![0u8; 2048]).unwrap();
+        fs::write(dir.path().join("sub/tiny.txt"), "xy").unwrap();
+        let args = Args::parse_from(["wisu".as_ref(), dir.path().as_os_str()]);
+        let tree = Tree::prepare(&args, false).unwrap();
+
+        let big = tree.find(|e| !e.is_directory && e.size.unwrap_or(0) > 1024);
+        assert_eq!(big.len(), 1);
+        assert!(big[0].path.ends_with("sub/big.bin"));
+        assert!(tree.find(|e| e.size.unwrap_or(0) > 1 << 20).is_empty());
+        assert_eq!(tree.find(|e| e.is_directory).len(), 1);
+    }
+
+    #[test]
+    fn test_entry_hook_tags() {
+        let dir = tempdir().unwrap();
+        fs::File::create(dir.path().join("main.rs")).unwrap();
+        fs::File::create(dir.path().join("notes.txt")).unwrap();
+
+        // Scoped to this tempdir, since other tests prepare trees concurrently
+        let root = dir.path().to_path_buf();
+        add_filter("entry", move |mut entry: TreeEntry| {
+            if entry.path.starts_with(&root)
+                && entry.path.extension().is_some_and(|ext| ext == "rs")
+            {
+                entry.tags.push("rust".to_string());
+            }
+            entry
+        });
+        let args = Args::parse_from(["wisu".as_ref(), dir.path().as_os_str()]);
+        let tree = Tree::prepare(&args, false);
+        remove_filters("entry");
+        let tree = tree.unwrap();
+
+        let label = |name: &str| {
+            tree.tree_info.iter().find(|e| e.path.ends_with(name)).unwrap().tags_label()
+        };
+        assert_eq!(label("main.rs").as_deref(), Some("[rust]"));
+        assert_eq!(label("notes.txt"), None);
+    }
+}
@@ -13,6 +13,8 @@ use app::Args;
 #[cfg(windows)]
 use colored::control;
 use lscolors::LsColors;
+use std::io::{self, Write};
+use utils::dir::{canonicalize_path, trim_trailing_separators};
 
 // include generated by build.rs
 include!("../plugins/plugins_mod.rs");
@@ -37,12 +39,27 @@ fn main() -> anyhow::Result<()> {
     control::set_override(true);
 
     // Parse the command-line arguments into our Args struct.
-    let mut args = apply_filter("parse_args", Args::load());
+    let mut args = apply_filter("parse_args", Args::load()?);
 
-    if !args.path.is_dir() {
+    // `src/` and `src` must display and export the same way
+    args.path = trim_trailing_separators(&args.path);
+
+    if args.print_config {
+        print!("{}", toml::to_string(&args)?);
+        return Ok(());
+    }
+
+    // Exports also accept a single file
+    let is_export = workers::export::OutputFormat::from_args(&args).is_some();
+    if !(args.path.is_dir() || is_export && args.path.is_file()) {
         anyhow::bail!("'{}' is not a directory.", args.path.display());
     }
 
+    // Only the displayed root changes: the walk enters a symlinked root with or without it
+    if args.dereference_args && args.path.is_symlink() {
+        args.path = canonicalize_path(&args.path);
+    }
+
     if args.info {
         args.size = true;
     }
@@ -51,18 +68,57 @@ fn main() -> anyhow::Result<()> {
         args.files = Some(0);
     }
 
-    if args.out.is_some() {
-        return workers::export(&args);
+    if args.no_recursion {
+        args.level = Some(1);
+    }
+
+    if args.long {
+        args.format = app::Format::Long;
+    }
+
+    if is_export {
+        if args.watch {
+            return workers::watch::run(&args);
+        }
+        let listed = workers::export(&args)?;
+        return exit_if_empty(&args, Some(listed));
+    }
+
+    if let Some(out) = &args.checksums {
+        return workers::checksum::write_manifest(&args, out);
+    }
+
+    if let Some(manifest) = &args.checksum_manifest {
+        return workers::checksum::verify_manifest(&args, manifest);
+    }
+
+    if args.dedupe_by_content {
+        return workers::checksum::report_duplicates(&args);
     }
 
     // LsColors instance from the environment
     let ls_colors = LsColors::from_env().unwrap_or_default();
 
-    let res = if args.interactive {
-        workers::tui::run(&args, &ls_colors)
+    // The TUI has no listing to report; the classic view has none in watch mode
+    let output = if args.interactive {
+        workers::tui::run(&args, &ls_colors).map(|()| None)
     } else {
         workers::view::run(&args, &ls_colors)
     };
+    let (res, listed) = match output {
+        Ok(listed) => (Ok(()), listed),
+        Err(e) => (Err(e), None),
+    };
+
+    apply_filter("on_exit", res)?;
+    exit_if_empty(&args, listed)
+}
 
-    apply_filter("on_exit", res)
+/// `--fail-if-empty`: exits with status 1, like `grep`, once an output listed nothing
+fn exit_if_empty(args: &Args, listed: Option<usize>) -> anyhow::Result<()> {
+    if args.fail_if_empty && listed == Some(0) {
+        io::stdout().flush()?;
+        std::process::exit(1);
+    }
+    Ok(())
 }
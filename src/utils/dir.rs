@@ -1,7 +1,9 @@
+#[cfg(unix)]
+use std::collections::HashMap;
 use std::fs::Metadata;
 use std::path::{Path, PathBuf};
 #[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
 
 /// Restituisce un percorso canonico/assoluto cross-platform senza prefisso \\?\ su Windows
 /// Il percorso passato deve essere già assoluto
@@ -13,8 +15,7 @@ pub fn canonicalize_path(path: &Path) -> PathBuf {
     // Rimuove prefisso \\?\ su Windows
     #[cfg(windows)]
     {
-        let s = abs.as_os_str().to_string_lossy();
-        PathBuf::from(s.strip_prefix(r"\\?\").unwrap_or(&s))
+        PathBuf::from(strip_verbatim_prefix(&abs.as_os_str().to_string_lossy()))
     }
     #[cfg(not(windows))]
     {
@@ -22,7 +23,31 @@ pub fn canonicalize_path(path: &Path) -> PathBuf {
     }
 }
 
+/// Percorso da mostrare all'utente (view, breadcrumb TUI, export), senza prefissi \\?\
+#[inline]
+pub fn display_path(path: &Path) -> String {
+    #[cfg(windows)]
+    {
+        strip_verbatim_prefix(&path.as_os_str().to_string_lossy())
+    }
+    #[cfg(not(windows))]
+    {
+        path.display().to_string()
+    }
+}
+
+/// Converte i percorsi estesi: \\?\UNC\server\share → \\server\share, \\?\C:\ → C:\
+#[cfg(windows)]
+fn strip_verbatim_prefix(path: &str) -> String {
+    if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{unc}")
+    } else {
+        path.strip_prefix(r"\\?\").unwrap_or(path).to_string()
+    }
+}
+
 /// Normalizza il percorso rimuovendo "." e ".." senza controllare l'esistenza
+/// I ".." iniziali dei percorsi relativi vengono mantenuti, la radice non si supera mai
 #[inline]
 fn normalize_path(path: &Path) -> PathBuf {
     use std::path::Component;
@@ -30,31 +55,181 @@ fn normalize_path(path: &Path) -> PathBuf {
     for comp in path.components() {
         match comp {
             Component::CurDir => {}
-            Component::ParentDir => {
-                comps.pop();
-            }
+            Component::ParentDir => match comps.last() {
+                Some(Component::Normal(_)) => {
+                    comps.pop();
+                }
+                // "/.." resta "/"
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => comps.push(comp),
+            },
             other => comps.push(other),
         }
     }
+    if comps.is_empty() {
+        return PathBuf::from(".");
+    }
     comps.iter().collect()
 }
 
+/// Rimuove i separatori finali ("src/" → "src"), lasciando intatta la radice
 #[inline]
-pub fn get_permission(metadata: Option<Metadata>) -> String {
+pub fn trim_trailing_separators(path: &Path) -> PathBuf {
+    path.components().collect()
+}
+
+// Cross-platform function to check if a file is executable
+#[inline]
+pub fn is_executable(path: &Path, metadata: &Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        let _ = path;
+        metadata.permissions().mode() & 0o111 != 0
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = metadata;
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| matches!(ext.to_lowercase().as_str(), "exe" | "bat" | "cmd"))
+            .unwrap_or(false)
+    }
+}
+
+/// Size of a file: allocated blocks with `disk_usage` (like `du`, Unix only),
+/// otherwise the apparent length
+#[inline]
+pub fn file_size(metadata: &Metadata, disk_usage: bool) -> u64 {
+    #[cfg(unix)]
+    if disk_usage {
+        return metadata.blocks() * 512;
+    }
+    #[cfg(not(unix))]
+    let _ = disk_usage;
+    metadata.len()
+}
+
+/// Resolves `user group` names for `--owner`, caching lookups by id;
+/// ids without a name are shown as numbers
+#[derive(Default)]
+pub struct OwnerNames {
+    #[cfg(unix)]
+    users: HashMap<u32, String>,
+    #[cfg(unix)]
+    groups: HashMap<u32, String>,
+}
+
+impl OwnerNames {
+    /// `user group ` (with a trailing space like [`get_permission`]); empty off Unix
+    pub fn owner(&mut self, metadata: &Metadata) -> String {
+        #[cfg(unix)]
+        {
+            let (uid, gid) = (metadata.uid(), metadata.gid());
+            let user = self.users.entry(uid).or_insert_with(|| {
+                uzers::get_user_by_uid(uid)
+                    .map_or_else(|| uid.to_string(), |u| u.name().to_string_lossy().into_owned())
+            });
+            let user = user.clone();
+            let group = self.groups.entry(gid).or_insert_with(|| {
+                uzers::get_group_by_gid(gid)
+                    .map_or_else(|| gid.to_string(), |g| g.name().to_string_lossy().into_owned())
+            });
+            format!("{user} {group} ")
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = metadata;
+            String::new()
+        }
+    }
+}
+
+/// How [`get_permission`] spells the permission bits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PermsStyle {
+    /// `rwxr-xr-x`
+    Symbolic,
+    /// `755` (`--perms-octal`)
+    Octal,
+}
+
+#[inline]
+pub fn get_permission(metadata: Option<Metadata>, style: PermsStyle) -> String {
+    let unknown = match style {
+        PermsStyle::Symbolic => "----------",
+        PermsStyle::Octal => "----",
+    };
     let perms = if let Some(md) = metadata {
         #[cfg(unix)]
         {
             let mode = md.permissions().mode();
             let ft_char = if md.is_dir() { 'd' } else { '-' };
-            format!("{}{}", ft_char, super::format::format_permissions(mode))
+            let bits = match style {
+                PermsStyle::Symbolic => super::format::format_permissions(mode),
+                PermsStyle::Octal => super::format::format_permissions_octal(mode),
+            };
+            format!("{ft_char}{bits}")
         }
         #[cfg(not(unix))]
         {
             let _ = md;
-            "----------".to_string()
+            unknown.to_string()
         }
     } else {
-        "----------".to_string()
+        unknown.to_string()
     };
     format!("{perms} ")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path(Path::new("../a/b")), Path::new("../a/b"));
+        assert_eq!(normalize_path(Path::new("a/../../b")), Path::new("../b"));
+        assert_eq!(normalize_path(Path::new("../../a/./b/..")), Path::new("../../a"));
+        assert_eq!(normalize_path(Path::new("a/..")), Path::new("."));
+    }
+
+    #[test]
+    fn test_trim_trailing_separators() {
+        assert_eq!(trim_trailing_separators(Path::new("src/")).as_os_str(), "src");
+        assert_eq!(trim_trailing_separators(Path::new("a/b//")).as_os_str(), "a/b");
+        assert_eq!(trim_trailing_separators(Path::new("src")).as_os_str(), "src");
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_display_path_strips_verbatim_prefixes() {
+        assert_eq!(display_path(Path::new(r"\\?\UNC\server\share\dir")), r"\\server\share\dir");
+        assert_eq!(display_path(Path::new(r"\\?\C:\Users\me")), r"C:\Users\me");
+        assert_eq!(display_path(Path::new(r"C:\Users\me")), r"C:\Users\me");
+        assert_eq!(
+            canonicalize_path(Path::new(r"\\?\UNC\server\share\dir")),
+            Path::new(r"\\server\share\dir")
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_disk_usage_of_sparse_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = std::fs::File::create(dir.path().join("sparse")).unwrap();
+        file.set_len(8 * 1024 * 1024).unwrap();
+        let metadata = file.metadata().unwrap();
+
+        assert_eq!(file_size(&metadata, false), 8 * 1024 * 1024);
+        assert!(file_size(&metadata, true) < file_size(&metadata, false));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_normalize_absolute_path() {
+        assert_eq!(normalize_path(Path::new("/a/../b")), Path::new("/b"));
+        assert_eq!(normalize_path(Path::new("/../a")), Path::new("/a"));
+        assert_eq!(normalize_path(Path::new("/a/..")), Path::new("/"));
+    }
+}
@@ -1,27 +1,155 @@
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 
+/// Binary size unit shared by size parsing and every size display
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SizeUnit {
+    #[value(name = "B")]
+    B,
+    #[value(name = "KiB")]
+    KiB,
+    #[value(name = "MiB")]
+    MiB,
+    #[value(name = "GiB")]
+    GiB,
+    #[value(name = "TiB")]
+    TiB,
+}
+
+impl SizeUnit {
+    const ALL: [SizeUnit; 5] =
+        [SizeUnit::B, SizeUnit::KiB, SizeUnit::MiB, SizeUnit::GiB, SizeUnit::TiB];
+
+    /// Number of bytes in one unit
+    pub fn bytes(self) -> u64 {
+        match self {
+            SizeUnit::B => 1,
+            SizeUnit::KiB => 1 << 10,
+            SizeUnit::MiB => 1 << 20,
+            SizeUnit::GiB => 1 << 30,
+            SizeUnit::TiB => 1 << 40,
+        }
+    }
+
+    /// Largest unit not exceeding `bytes`, used for human-readable sizes
+    pub fn auto(bytes: u64) -> Self {
+        Self::ALL.into_iter().rev().find(|unit| bytes >= unit.bytes()).unwrap_or(SizeUnit::B)
+    }
+
+    /// Parses a unit suffix; `K`, `KB` and `KiB` all mean 1024 bytes, and no suffix means bytes
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix.trim().to_lowercase().as_str() {
+            "" | "b" => Some(SizeUnit::B),
+            "k" | "kb" | "kib" => Some(SizeUnit::KiB),
+            "m" | "mb" | "mib" => Some(SizeUnit::MiB),
+            "g" | "gb" | "gib" => Some(SizeUnit::GiB),
+            "t" | "tb" | "tib" => Some(SizeUnit::TiB),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SizeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SizeUnit::B => "B",
+            SizeUnit::KiB => "KiB",
+            SizeUnit::MiB => "MiB",
+            SizeUnit::GiB => "GiB",
+            SizeUnit::TiB => "TiB",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A size given on the command line or in the config, e.g. `500K`, `1.5MiB`, `2G`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ByteSize(pub u64);
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_size(s).map(ByteSize)
+    }
+}
+
+/// Serialized as a plain byte count, which parses back to the same size
+impl Serialize for ByteSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+impl TryFrom<String> for ByteSize {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Parses a size with an optional unit suffix (see [`SizeUnit::from_suffix`]), e.g. `1.5MiB`
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(s.len());
+    let (number, suffix) = s.split_at(split);
+
+    let value: f64 = number.parse().map_err(|_| format!("Invalid size: '{s}'"))?;
+    let unit = SizeUnit::from_suffix(suffix)
+        .ok_or_else(|| format!("Invalid size unit in '{s}' (use B, K, M, G or T)"))?;
+
+    Ok((value * unit.bytes() as f64).round() as u64)
+}
 
 /// Formats a size in bytes into a human-readable string using binary prefixes (KiB, MiB).
 pub fn size(bytes: u64) -> String {
-    const KIB: f64 = 1024.0;
-    const MIB: f64 = KIB * 1024.0;
-    const GIB: f64 = MIB * 1024.0;
-    const TIB: f64 = GIB * 1024.0;
-
-    let bytes = bytes as f64;
-
-    if bytes < KIB {
-        format!("{bytes} B")
-    } else if bytes < MIB {
-        format!("{:.1} KiB", bytes / KIB)
-    } else if bytes < GIB {
-        format!("{:.1} MiB", bytes / MIB)
-    } else if bytes < TIB {
-        format!("{:.1} GiB", bytes / GIB)
+    size_with(bytes, SizeUnit::auto(bytes))
+}
+
+/// Formats a size in bytes in the given unit, e.g. for columns of comparable sizes.
+pub fn size_with(bytes: u64, unit: SizeUnit) -> String {
+    match unit {
+        SizeUnit::B => format!("{bytes} B"),
+        _ => format!("{:.1} {unit}", bytes as f64 / unit.bytes() as f64),
+    }
+}
+
+/// Formats a size in bytes with decimal SI prefixes (kB, MB at 1000), like `du --si`.
+pub fn size_si(bytes: u64) -> String {
+    const PREFIXES: [&str; 4] = ["kB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+    let exponent = ((bytes as f64).log10() as u32 / 3).min(PREFIXES.len() as u32);
+    let value = bytes as f64 / 1000f64.powi(exponent as i32);
+    format!("{value:.1} {}", PREFIXES[exponent as usize - 1])
+}
+
+/// Formats a duration compactly with one consistent unit per range: `850µs`, `340ms`, `1.2s`, `2m 05s`.
+pub fn duration(duration: Duration) -> String {
+    let micros = duration.as_micros();
+    if micros < 1_000 {
+        format!("{micros}µs")
+    } else if micros < 1_000_000 {
+        format!("{}ms", micros / 1_000)
+    } else if duration.as_secs() < 60 {
+        format!("{:.1}s", duration.as_secs_f64())
     } else {
-        format!("{:.1} TiB", bytes / TIB)
+        format!("{}m {:02}s", duration.as_secs() / 60, duration.as_secs() % 60)
     }
 }
 
+/// Formats a timestamp as local `YYYY-MM-DD HH:MM`, like `ls -l --time-style=long-iso`.
+pub fn datetime(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M").to_string()
+}
+
 /// Formats a Unix file mode into a human-readable string (e.g., "rwxr-xr-x").
 #[cfg(unix)]
 pub fn format_permissions(mode: u32) -> String {
@@ -40,6 +168,12 @@ pub fn format_permissions(mode: u32) -> String {
     PERMISSIONS.iter().map(|&(bit, c)| if mode & bit != 0 { c } else { '-' }).collect()
 }
 
+/// Formats the permission bits of a Unix file mode in octal (e.g., "755", or "4755" with setuid).
+#[cfg(unix)]
+pub fn format_permissions_octal(mode: u32) -> String {
+    format!("{:03o}", mode & 0o7777)
+}
+
 // Unit tests for utility functions
 #[cfg(test)]
 mod tests {
@@ -57,6 +191,55 @@ mod tests {
         assert_eq!(size(gib), "1.0 GiB");
     }
 
+    #[test]
+    fn test_format_size_with() {
+        let gib = 1024 * 1024 * 1024;
+        assert_eq!(size_with(gib, SizeUnit::MiB), "1024.0 MiB");
+        assert_eq!(size_with(512 * 1024, SizeUnit::MiB), "0.5 MiB");
+        assert_eq!(size_with(1536, SizeUnit::B), "1536 B");
+        assert_eq!(size_with(0, SizeUnit::KiB), "0.0 KiB");
+    }
+
+    #[test]
+    fn test_size_round_trip() {
+        let bytes = parse_size("1.5MiB").unwrap();
+        assert_eq!(bytes, 1536 * 1024);
+        assert_eq!(size_with(bytes, SizeUnit::MiB), "1.5 MiB");
+        assert_eq!(size(bytes), "1.5 MiB");
+        assert_eq!(parse_size(&size(bytes)), Ok(bytes));
+        assert_eq!(SizeUnit::auto(1023), SizeUnit::B);
+        assert_eq!(SizeUnit::auto(1 << 30), SizeUnit::GiB);
+    }
+
+    #[test]
+    fn test_size_si() {
+        assert_eq!(size_si(1000), "1.0 kB");
+        assert_eq!(size(1000), "1000 B");
+        assert_eq!(size_si(999), "999 B");
+        assert_eq!(size_si(1_500_000), "1.5 MB");
+        assert_eq!(size_si(2_000_000_000_000_000), "2000.0 TB");
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("100"), Ok(100));
+        assert_eq!(parse_size("500K"), Ok(500 * 1024));
+        assert_eq!(parse_size("1.5 MiB"), Ok(1536 * 1024));
+        assert_eq!(parse_size("2g"), Ok(2 << 30));
+        assert!(parse_size("12Q").is_err());
+        assert!(parse_size("MiB").is_err());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(duration(Duration::from_micros(850)), "850µs");
+        assert_eq!(duration(Duration::from_micros(1_230)), "1ms");
+        assert_eq!(duration(Duration::from_millis(340)), "340ms");
+        assert_eq!(duration(Duration::from_millis(1_234)), "1.2s");
+        assert_eq!(duration(Duration::from_secs(59)), "59.0s");
+        assert_eq!(duration(Duration::from_secs(125)), "2m 05s");
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_format_permissions() {
@@ -70,4 +253,15 @@ mod tests {
         let mode_user_only = 0o700;
         assert_eq!(format_permissions(mode_user_only), "rwx------");
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_format_permissions_octal() {
+        assert_eq!(format_permissions_octal(0o755), "755");
+        assert_eq!(format_permissions_octal(0o640), "640");
+        assert_eq!(format_permissions_octal(0o007), "007");
+        // File type bits are dropped, special bits kept
+        assert_eq!(format_permissions_octal(0o100644), "644");
+        assert_eq!(format_permissions_octal(0o104755), "4755");
+    }
 }
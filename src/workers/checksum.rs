@@ -0,0 +1,226 @@
+use crate::app::Args;
+use crate::common::tree::Tree;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Relative path (with `/` separators) → lowercase hex SHA-256
+pub type Manifest = BTreeMap<String, String>;
+
+/// Differences between a manifest and the current tree
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ManifestDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+impl ManifestDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Writes a `sha256sum`-compatible manifest of every file in the tree
+pub fn write_manifest(args: &Args, out: &Path) -> Result<()> {
+    let manifest = hash_tree(args)?;
+    let text: String = manifest.iter().map(|(path, hash)| format!("{hash}  {path}\n")).collect();
+    fs::write(out, text).with_context(|| format!("Cannot write manifest {}", out.display()))?;
+    println!("Wrote checksums of {} files to {}", manifest.len(), out.display());
+    Ok(())
+}
+
+/// Compares the tree against a manifest, failing when any file was added, removed or modified
+pub fn verify_manifest(args: &Args, manifest_path: &Path) -> Result<()> {
+    let text = fs::read_to_string(manifest_path)
+        .with_context(|| format!("Cannot read manifest {}", manifest_path.display()))?;
+    let expected = parse_manifest(&text)?;
+    let diff = compare(&expected, &hash_tree(args)?);
+
+    for (label, paths) in
+        [("added", &diff.added), ("removed", &diff.removed), ("modified", &diff.modified)]
+    {
+        for path in paths {
+            println!("{label:<9}{path}");
+        }
+    }
+    println!(
+        "{} added, {} removed, {} modified",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.modified.len()
+    );
+
+    if !diff.is_empty() {
+        anyhow::bail!("'{}' does not match {}", args.path.display(), manifest_path.display());
+    }
+    Ok(())
+}
+
+/// Files with identical content; every copy beyond the first is wasted space
+#[derive(Debug, PartialEq, Eq)]
+pub struct DuplicateSet {
+    pub size: u64,
+    pub paths: Vec<String>,
+}
+
+impl DuplicateSet {
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+/// Prints the sets of files with identical content, most wasted space first
+pub fn report_duplicates(args: &Args) -> Result<()> {
+    let tree = Tree::prepare(args, false)?;
+    let files = tree.find(|e| !e.is_directory).into_iter().map(|e| (&e.path, e.size.unwrap_or(0)));
+    let sets = find_duplicates(files, &args.path)?;
+
+    for set in &sets {
+        println!(
+            "{} copies of {}, {} wasted",
+            set.paths.len(),
+            args.format_size(set.size),
+            args.format_size(set.wasted())
+        );
+        for path in &set.paths {
+            println!("  {path}");
+        }
+        println!();
+    }
+    println!(
+        "{} duplicate sets, {} wasted",
+        sets.len(),
+        args.format_size(sets.iter().map(DuplicateSet::wasted).sum())
+    );
+    Ok(())
+}
+
+/// Groups files by size, then hashes only the sizes shared by several files
+fn find_duplicates<'a>(
+    files: impl IntoIterator<Item = (&'a PathBuf, u64)>,
+    root: &Path,
+) -> Result<Vec<DuplicateSet>> {
+    let mut by_size: BTreeMap<u64, Vec<&Path>> = BTreeMap::new();
+    for (path, size) in files {
+        // Empty files are all alike but waste nothing
+        if size > 0 {
+            by_size.entry(size).or_default().push(path);
+        }
+    }
+
+    let mut sets = Vec::new();
+    for (size, paths) in by_size.into_iter().filter(|(_, paths)| paths.len() > 1) {
+        let mut by_hash: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for path in paths {
+            by_hash.entry(hash_file(path)?).or_default().push(manifest_key(path, root));
+        }
+        for mut paths in by_hash.into_values().filter(|paths| paths.len() > 1) {
+            paths.sort();
+            sets.push(DuplicateSet { size, paths });
+        }
+    }
+    sets.sort_by(|a, b| b.wasted().cmp(&a.wasted()).then_with(|| a.paths.cmp(&b.paths)));
+    Ok(sets)
+}
+
+fn hash_tree(args: &Args) -> Result<Manifest> {
+    let tree = Tree::prepare(args, false)?;
+    let mut manifest = Manifest::new();
+    for entry in tree.find(|e| !e.is_directory) {
+        manifest.insert(manifest_key(&entry.path, &args.path), hash_file(&entry.path)?);
+    }
+    Ok(manifest)
+}
+
+/// Path relative to `root` with `/` separators
+fn manifest_key(path: &Path, root: &Path) -> String {
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    rel.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Parses `<hash>  <path>` lines; the `*` binary marker of `sha256sum -b` is accepted
+fn parse_manifest(text: &str) -> Result<Manifest> {
+    let mut manifest = Manifest::new();
+    for (n, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let (hash, path) = line
+            .split_once(' ')
+            .with_context(|| format!("Invalid manifest line {}: '{line}'", n + 1))?;
+        let path = path.strip_prefix([' ', '*']).unwrap_or(path);
+        manifest.insert(path.to_string(), hash.to_lowercase());
+    }
+    Ok(manifest)
+}
+
+fn compare(expected: &Manifest, actual: &Manifest) -> ManifestDiff {
+    let mut diff = ManifestDiff::default();
+    for (path, hash) in actual {
+        match expected.get(path) {
+            None => diff.added.push(path.clone()),
+            Some(old) if old != hash => diff.modified.push(path.clone()),
+            _ => {}
+        }
+    }
+    diff.removed = expected.keys().filter(|p| !actual.contains_key(*p)).cloned().collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_compare() {
+        let expected =
+            parse_manifest("aa  keep.txt\nbb *changed.txt\n\ncc  gone/old.txt\n").unwrap();
+        let actual: Manifest = [("keep.txt", "aa"), ("changed.txt", "dd"), ("new.txt", "ee")]
+            .map(|(p, h)| (p.to_string(), h.to_string()))
+            .into();
+
+        let diff = compare(&expected, &actual);
+        assert_eq!(diff.added, ["new.txt"]);
+        assert_eq!(diff.removed, ["gone/old.txt"]);
+        assert_eq!(diff.modified, ["changed.txt"]);
+        assert!(parse_manifest("no-separator").is_err());
+    }
+
+    #[test]
+    fn test_find_duplicates_by_wasted_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [("a", "small"), ("b", "small"), ("c", "other"), ("d", "large!"), ("e", "")];
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        for name in ["sub/d2", "sub/d3"] {
+            fs::write(dir.path().join(name), "large!").unwrap();
+        }
+        fs::write(dir.path().join("sub/e2"), "").unwrap();
+
+        let paths: Vec<PathBuf> = ["a", "b", "c", "d", "e", "sub/d2", "sub/d3", "sub/e2"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        let files = paths.iter().map(|p| (p, fs::metadata(p).unwrap().len()));
+        let sets = find_duplicates(files, dir.path()).unwrap();
+
+        assert_eq!(
+            sets,
+            [
+                DuplicateSet { size: 6, paths: vec!["d".into(), "sub/d2".into(), "sub/d3".into()] },
+                DuplicateSet { size: 5, paths: vec!["a".into(), "b".into()] },
+            ]
+        );
+        assert_eq!(sets[0].wasted(), 12);
+    }
+}
@@ -1,8 +1,80 @@
-use crate::app::Args;
+use crate::app::{Args, Format};
 use crate::common::tree::{TreeEntry, Tree};
-use crate::utils::dir::get_permission;
-use anyhow::Result;
+use crate::utils::dir::{display_path, file_size, get_permission};
+use crate::utils::format;
+use anyhow::{Context, Result};
+use serde::ser::{SerializeSeq, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// File count and total size for one extension in the `by_extension` summary
+#[derive(Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct ExtensionStats {
+    pub count: u64,
+    pub total_size: u64,
+}
+
+/// Scan duration and throughput, for tracking performance across automated runs
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct ScanStats {
+    pub entries: usize,
+    pub duration_ms: f64,
+    pub entries_per_sec: f64,
+}
+
+impl ScanStats {
+    pub fn new(entries: usize, elapsed: std::time::Duration) -> Self {
+        let secs = elapsed.as_secs_f64();
+        ScanStats {
+            entries,
+            duration_ms: secs * 1000.0,
+            entries_per_sec: if secs > 0.0 { entries as f64 / secs } else { 0.0 },
+        }
+    }
+}
+
+/// `--stats-json` sidecar: the stats line as JSON, the scan throughput and the number
+/// of entries at each depth. Also the only place for the scan stats of CSV, TSV,
+/// NDJSON and `--stream` exports, which have no document root to carry them,
+/// unlike the JSON, YAML and XML root node.
+#[derive(Debug, serde::Serialize)]
+pub struct StatsSummary {
+    pub dirs: usize,
+    pub files: usize,
+    pub size: u64,
+    #[serde(flatten)]
+    pub scan: ScanStats,
+    pub depths: BTreeMap<usize, usize>,
+}
+
+impl StatsSummary {
+    /// Counts and root size of a scanned tree
+    pub fn from_tree(tree: &Tree, scan: ScanStats) -> Self {
+        let dirs = tree.tree_info.iter().filter(|info| info.is_directory).count();
+        StatsSummary {
+            dirs,
+            files: tree.tree_info.len() - dirs,
+            size: tree.entries_at_depth(1).iter().map(|(_, info)| info.size.unwrap_or(0)).sum(),
+            scan,
+            depths: depth_counts(tree),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Number of entries at each depth of `tree`
+pub fn depth_counts(tree: &Tree) -> BTreeMap<usize, usize> {
+    tree.tree_info.iter().fold(BTreeMap::new(), |mut depths, info| {
+        *depths.entry(info.depth).or_default() += 1;
+        depths
+    })
+}
 
 #[derive(Debug, serde::Serialize)]
 pub struct ExportNode {
@@ -14,45 +86,148 @@ pub struct ExportNode {
     pub file_count: Option<u64>,
     pub permissions: String,
     pub children: Option<Vec<ExportNode>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_extension: Option<BTreeMap<String, ExtensionStats>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan: Option<ScanStats>,
+    /// Directory whose children were cut by `--export-depth`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
 }
 
 pub enum OutputFormat {
     Json,
     Xml,
     Csv,
+    Tsv,
+    Yaml,
+    Ndjson,
 }
 
 impl OutputFormat {
-    pub fn from_str(s: &Option<String>) -> Option<Self> {
-        match s.as_deref()?.to_lowercase().as_str() {
-            "json" => Some(Self::Json),
-            "xml" => Some(Self::Xml),
-            "csv" => Some(Self::Csv),
-            _ => None,
+    /// The requested export format: `-o TYPE`, else an export `--format`
+    pub fn from_args(args: &Args) -> Option<Self> {
+        if let Some(out) = &args.out {
+            return match out.to_lowercase().as_str() {
+                "json" => Some(Self::Json),
+                "xml" => Some(Self::Xml),
+                "csv" => Some(Self::Csv),
+                "tsv" => Some(Self::Tsv),
+                "yaml" | "yml" => Some(Self::Yaml),
+                "ndjson" | "jsonl" => Some(Self::Ndjson),
+                _ => None,
+            };
         }
+
+        match args.format {
+            Format::Json => Some(Self::Json),
+            Format::Xml => Some(Self::Xml),
+            Format::Csv => Some(Self::Csv),
+            Format::Tsv => Some(Self::Tsv),
+            Format::Yaml => Some(Self::Yaml),
+            Format::Ndjson => Some(Self::Ndjson),
+            Format::Tree | Format::Table | Format::Long => None,
+        }
+    }
+}
+
+/// Export destination: `--output`, the legacy `export.<TYPE>` of `-o`, or stdout
+fn output_path(args: &Args) -> Option<PathBuf> {
+    match (&args.output, &args.out) {
+        (Some(path), _) => Some(path.clone()),
+        (None, Some(out)) => Some(PathBuf::from(format!("export.{out}"))),
+        (None, None) => None,
     }
 }
 
-pub fn export(args: &Args) -> Result<()> {
-    if !args.path.is_dir() {
-        anyhow::bail!("'{}' is not a directory.", args.path.display());
+/// `--relative-to`: node paths relative to BASE instead of `./<root>/...`
+struct RelativeTo {
+    root: PathBuf,
+    base: PathBuf,
+}
+
+impl RelativeTo {
+    fn from_args(args: &Args) -> Result<Option<Self>> {
+        let Some(base) = &args.relative_to else { return Ok(None) };
+        let base = fs::canonicalize(base)
+            .with_context(|| format!("Invalid --relative-to base '{}'", base.display()))?;
+        Ok(Some(RelativeTo { root: fs::canonicalize(&args.path)?, base }))
     }
 
+    /// Path of `rel` (relative to the scan root), absolute when BASE is not an ancestor
+    fn path(&self, rel: &Path) -> String {
+        let full = if rel.as_os_str().is_empty() { self.root.clone() } else { self.root.join(rel) };
+        match full.strip_prefix(&self.base) {
+            Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+            Ok(rel) => format!("./{}", rel.display()),
+            Err(_) => display_path(&full),
+        }
+    }
+}
+
+/// Exports the tree, returning how many files (directories with `-d`) it lists
+pub fn export(args: &Args) -> Result<usize> {
+    export_snapshot(args, None)
+}
+
+/// NDJSON record opening each `--watch` snapshot, so consumers can tell them apart
+#[derive(Debug, serde::Serialize)]
+struct SnapshotHeader {
+    /// 1 for the initial snapshot, then one more after every change
+    snapshot: u64,
+}
+
+/// Like [`export`]; `snapshot` numbers the output of one `--watch` round
+pub fn export_snapshot(args: &Args, snapshot: Option<u64>) -> Result<usize> {
+    if !args.path.is_dir() && !args.path.is_file() {
+        anyhow::bail!("'{}' is not a directory or a file.", args.path.display());
+    }
+    // Fail early on a bad base; the builders below resolve it again
+    RelativeTo::from_args(args)?;
+
     let start = std::time::Instant::now();
 
     // ───────────── Data Preparation ─────────────
-    let tree = Tree::prepare(args, true)?;
+    let out_path = output_path(args);
+    // A single file is exported as one node, without walking anything
+    let tree = if args.path.is_file() {
+        None
+    } else {
+        // Progress output would end up in an export streamed to stdout
+        Some(Tree::prepare(args, out_path.is_some())?)
+    };
+    let scan = ScanStats::new(tree.as_ref().map_or(1, |t| t.entries.len()), start.elapsed());
+    if let Some(path) = &args.stats_json {
+        let summary = match &tree {
+            Some(tree) => StatsSummary::from_tree(tree, scan),
+            None => StatsSummary {
+                dirs: 0,
+                files: 1,
+                size: build_file_node(args)?.size.unwrap_or(0),
+                scan,
+                depths: BTreeMap::new(),
+            },
+        };
+        summary.write(path)?;
+    }
 
-    let format = OutputFormat::from_str(&args.out).ok_or_else(|| {
+    let format = OutputFormat::from_args(args).ok_or_else(|| {
         anyhow::anyhow!("Invalid format: {}", args.out.clone().unwrap_or_default())
     })?;
 
-    let out_path = format!("export.{}", args.out.as_ref().unwrap());
+    let mut out: Box<dyn Write> = match &out_path {
+        Some(path) => Box::new(io::BufWriter::new(fs::File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    };
 
     match format {
-        OutputFormat::Csv => {
-            let flat_nodes = build_export_flat_list(&tree, args)?;
-            let mut wtr = csv::Writer::from_path(out_path)?;
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let mut builder = csv::WriterBuilder::new();
+            if matches!(format, OutputFormat::Tsv) {
+                // Tab-free fields stay unquoted, for `cut` and `awk`
+                builder.delimiter(b'\t').quote_style(csv::QuoteStyle::Never);
+            }
+            let mut wtr = builder.from_writer(&mut out);
             wtr.write_record([
                 "path",
                 "name",
@@ -62,42 +237,166 @@ pub fn export(args: &Args) -> Result<()> {
                 "file_count",
                 "permissions",
             ])?;
-            for node in flat_nodes {
-                wtr.write_record([
-                    &node.path,
-                    &node.name,
-                    &node.is_dir.to_string(),
-                    &node.size.map_or(String::new(), |s| s.to_string()),
-                    &node.dir_count.map_or(String::new(), |d| d.to_string()),
-                    &node.file_count.map_or(String::new(), |f| f.to_string()),
-                    &node.permissions,
-                ])?;
+            match &tree {
+                Some(tree) if args.stream => {
+                    for_each_flat_node(tree, args, |node| write_csv_record(&mut wtr, &node))?
+                }
+                Some(tree) => {
+                    for node in build_export_flat_list(tree, args)? {
+                        write_csv_record(&mut wtr, &node)?;
+                    }
+                }
+                None => write_csv_record(&mut wtr, &build_file_node(args)?)?,
             }
             wtr.flush()?;
         }
-        OutputFormat::Json | OutputFormat::Xml => {
-            let export_root = build_export_tree(&tree, args);
+        OutputFormat::Ndjson => {
+            let nodes = match &tree {
+                Some(tree) => build_export_flat_list(tree, args)?,
+                None => vec![build_file_node(args)?],
+            };
+            if let Some(snapshot) = snapshot {
+                writeln!(out, "{}", serde_json::to_string(&SnapshotHeader { snapshot })?)?;
+            }
+            for node in nodes {
+                writeln!(out, "{}", serde_json::to_string(&node)?)?;
+            }
+        }
+        // Flat array instead of the nested tree, without holding every node in memory
+        OutputFormat::Json if args.stream => stream_json(&mut out, tree.as_ref(), args)?,
+        OutputFormat::Json | OutputFormat::Xml | OutputFormat::Yaml => {
+            let mut export_root = match &tree {
+                Some(tree) => build_export_tree(tree, args),
+                None => build_file_node(args)?,
+            };
+            if let Some(tree) = &tree
+                && args.group_by_ext
+                && matches!(format, OutputFormat::Json | OutputFormat::Yaml)
+            {
+                export_root.by_extension = Some(build_extension_summary(tree));
+            }
+            export_root.scan = Some(scan);
 
             match format {
-                OutputFormat::Json => {
-                    fs::write(out_path, serde_json::to_string_pretty(&export_root)?)?
+                OutputFormat::Json => serde_json::to_writer_pretty(&mut out, &export_root)?,
+                OutputFormat::Yaml => {
+                    out.write_all(serde_yaml::to_string(&export_root)?.as_bytes())?
+                }
+                OutputFormat::Xml => {
+                    out.write_all(serde_xml_rs::to_string(&export_root)?.as_bytes())?
                 }
-                OutputFormat::Xml => fs::write(out_path, serde_xml_rs::to_string(&export_root)?)?,
                 _ => {}
             }
         }
     }
+    out.flush()?;
 
-    println!("Export completed in {:.2?}", start.elapsed());
-    Ok(())
+    // On stderr, so an export streamed to stdout stays parseable
+    eprintln!("Export completed in {}", format::duration(start.elapsed()));
+
+    // A single file is one listed file
+    let (dirs, files) = tree.map_or((0, 1), |tree| {
+        let (dirs, files, _) = tree.totals();
+        (dirs, files)
+    });
+    Ok(if args.dirs_only { dirs } else { files })
+}
+
+/// Exports a single-file root path as one node, sized whatever the size flags
+fn build_file_node(args: &Args) -> Result<ExportNode> {
+    let metadata = fs::metadata(&args.path)?;
+    let name = args.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+    Ok(ExportNode {
+        path: match RelativeTo::from_args(args)? {
+            Some(relative_to) => relative_to.path(Path::new("")),
+            None => format!("./{name}"),
+        },
+        name,
+        is_dir: false,
+        size: Some(file_size(&metadata, args.disk_usage)),
+        dir_count: None,
+        file_count: None,
+        permissions: if args.permissions {
+            get_permission(Some(metadata), args.perms_style())
+        } else {
+            String::new()
+        },
+        children: None,
+        by_extension: None,
+        scan: None,
+        truncated: false,
+    })
+}
+
+/// Aggregates file count and size per lowercase extension ("" for files without one)
+fn build_extension_summary(tree: &Tree) -> BTreeMap<String, ExtensionStats> {
+    let mut summary: BTreeMap<String, ExtensionStats> = BTreeMap::new();
+
+    for info in tree.find(|info| !info.is_directory) {
+        let ext = info
+            .path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let stats = summary.entry(ext).or_default();
+        stats.count += 1;
+        stats.total_size += info.size.unwrap_or(0);
+    }
+
+    summary
+}
+
+/// Recursive directory and file counts of every listed directory, collapsed subtrees
+/// included. Counting listed entries keeps each node in line with the root totals,
+/// whatever `-F`, `--only-ext` or the size filters hid.
+fn listed_counts<'a>(tree: &'a Tree, root: &Path) -> HashMap<&'a Path, (u64, u64)> {
+    let mut counts: HashMap<&Path, (u64, u64)> = HashMap::new();
+    let mut add = |path, (dirs, files): (usize, usize)| {
+        let count = counts.entry(path).or_default();
+        count.0 += dirs as u64;
+        count.1 += files as u64;
+    };
+    for info in &tree.tree_info {
+        let collapsed = info.collapsed.unwrap_or_default();
+        if info.collapsed.is_some() {
+            add(info.path.as_path(), collapsed);
+        }
+        let own = if info.is_directory { (1, 0) } else { (0, 1) };
+        let listed = (own.0 + collapsed.0, own.1 + collapsed.1);
+        for ancestor in info.path.ancestors().skip(1).take_while(|a| a.starts_with(root)) {
+            add(ancestor, listed);
+        }
+    }
+    counts
+}
+
+/// Counts of a node: its listed contents for a directory, itself for a file
+fn node_counts(counts: &HashMap<&Path, (u64, u64)>, path: &Path, is_dir: bool) -> (u64, u64) {
+    if is_dir { counts.get(path).copied().unwrap_or_default() } else { (0, 1) }
 }
 
 /// Exports the tree as a flat list
 fn build_export_flat_list(tree: &Tree, args: &Args) -> Result<Vec<ExportNode>> {
+    let mut flat_nodes = Vec::new();
+    for_each_flat_node(tree, args, |node| {
+        flat_nodes.push(node);
+        Ok(())
+    })?;
+    Ok(flat_nodes)
+}
+
+/// Builds the flat list one node at a time, handing each to `emit` (`--stream`)
+fn for_each_flat_node(
+    tree: &Tree,
+    args: &Args,
+    mut emit: impl FnMut(ExportNode) -> Result<()>,
+) -> Result<()> {
     let default_info = TreeEntry::default();
     let canonical_root = fs::canonicalize(&args.path).unwrap_or(args.path.clone());
+    let relative_to = RelativeTo::from_args(args)?;
+    let counts = listed_counts(tree, &args.path);
 
-    let mut flat_nodes = Vec::new();
     for (idx, entry) in tree.entries.iter().enumerate() {
         if args.dirs_only && !entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
             continue;
@@ -105,10 +404,15 @@ fn build_export_flat_list(tree: &Tree, args: &Args) -> Result<Vec<ExportNode>> {
 
         let c_info = tree.tree_info.get(idx).unwrap_or(&default_info);
 
-        let permissions =
-            if args.permissions { get_permission(entry.metadata().ok()) } else { String::new() };
+        let permissions = if args.permissions {
+            get_permission(entry.metadata().ok(), args.perms_style())
+        } else {
+            String::new()
+        };
 
-        let display_path = if entry.path() == canonical_root {
+        let display_path = if let Some(relative_to) = &relative_to {
+            relative_to.path(entry.path().strip_prefix(&args.path).unwrap_or(entry.path()))
+        } else if entry.path() == canonical_root {
             format!("./{}", args.path.file_name().unwrap_or_default().to_string_lossy())
         } else if let Ok(rel) = entry.path().strip_prefix(&canonical_root) {
             format!(
@@ -117,34 +421,65 @@ fn build_export_flat_list(tree: &Tree, args: &Args) -> Result<Vec<ExportNode>> {
                 rel.display()
             )
         } else {
-            entry.path().display().to_string()
+            display_path(entry.path())
         };
 
-        flat_nodes.push(ExportNode {
+        let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(true);
+        let (dir_count, file_count) = node_counts(&counts, entry.path(), is_dir);
+        emit(ExportNode {
             name: entry.file_name().to_string_lossy().to_string(),
             path: display_path,
-            is_dir: entry.file_type().map(|ft| ft.is_dir()).unwrap_or(true),
+            is_dir,
             size: c_info.size,
-            dir_count: c_info.dirs,
-            file_count: c_info.files,
+            dir_count: Some(dir_count),
+            file_count: Some(file_count),
             permissions,
             children: None,
-        });
+            by_extension: None,
+            scan: None,
+            truncated: false,
+        })?;
     }
 
-    Ok(flat_nodes)
+    Ok(())
+}
+
+/// Writes the flat list as a JSON array, serializing each node as soon as it is built
+fn stream_json(out: &mut impl Write, tree: Option<&Tree>, args: &Args) -> Result<()> {
+    let mut serializer = serde_json::Serializer::new(out);
+    let mut seq = serializer.serialize_seq(None)?;
+    match tree {
+        Some(tree) => for_each_flat_node(tree, args, |node| Ok(seq.serialize_element(&node)?))?,
+        None => seq.serialize_element(&build_file_node(args)?)?,
+    }
+    seq.end()?;
+    Ok(())
+}
+
+fn write_csv_record(wtr: &mut csv::Writer<impl Write>, node: &ExportNode) -> Result<()> {
+    wtr.write_record([
+        &node.path,
+        &node.name,
+        &node.is_dir.to_string(),
+        &node.size.map_or(String::new(), |s| s.to_string()),
+        &node.dir_count.map_or(String::new(), |d| d.to_string()),
+        &node.file_count.map_or(String::new(), |f| f.to_string()),
+        &node.permissions,
+    ])?;
+    Ok(())
 }
 
 /// Exports the tree as a hierarchical structure
 fn build_export_tree(tree: &Tree, args: &Args) -> ExportNode {
-    use std::collections::HashMap;
-    use std::path::{Path, PathBuf};
-
     let root_path = &args.path;
+    // Validated by `export`
+    let relative_to = RelativeTo::from_args(args).ok().flatten();
 
     // ────────────────────────────────
     //  Build parent → children map
     // ────────────────────────────────
+    // `tree.entries` is already sorted depth-first, so pushing in that order keeps
+    // each directory's children in exactly the order the view prints them
     let mut children_map: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
 
     for entry in &tree.entries {
@@ -157,6 +492,11 @@ fn build_export_tree(tree: &Tree, args: &Args) -> ExportNode {
         children_map.entry(rel_parent).or_default().push(rel_child);
     }
 
+    // Aggregated sizes, as shown by `--info`, and counts of the listed entries
+    let infos: HashMap<&Path, &TreeEntry> =
+        tree.tree_info.iter().map(|info| (info.path.as_path(), info)).collect();
+    let counts = listed_counts(tree, root_path);
+
     // ────────────────────────────────
     //  Recursive function to build nodes
     // ────────────────────────────────
@@ -164,25 +504,24 @@ fn build_export_tree(tree: &Tree, args: &Args) -> ExportNode {
         rel_path: &Path,
         root_path: &Path,
         children_map: &HashMap<PathBuf, Vec<PathBuf>>,
+        infos: &HashMap<&Path, &TreeEntry>,
+        counts: &HashMap<&Path, (u64, u64)>,
+        relative_to: Option<&RelativeTo>,
         args: &Args,
     ) -> ExportNode {
         let full_path = root_path.join(rel_path);
-        let is_dir = full_path.is_dir();
-        let metadata = full_path.metadata().ok();
-
-        let size = if args.size || args.info {
-            metadata.as_ref().map(|m| m.len())
-        } else {
-            None
-        };
+        let info = infos.get(full_path.as_path());
+        let is_dir = info.map_or_else(|| full_path.is_dir(), |info| info.is_directory);
 
         let permissions = if args.permissions {
-            get_permission(metadata)
+            get_permission(full_path.metadata().ok(), args.perms_style())
         } else {
             String::new()
         };
 
-        let display_path = if rel_path.as_os_str().is_empty() {
+        let display_path = if let Some(relative_to) = relative_to {
+            relative_to.path(rel_path)
+        } else if rel_path.as_os_str().is_empty() {
             format!(
                 "./{}",
                 root_path.file_name().unwrap_or_default().to_string_lossy()
@@ -195,11 +534,22 @@ fn build_export_tree(tree: &Tree, args: &Args) -> ExportNode {
             )
         };
 
-        // Recursively build children
+        // Recursively build children, down to `--export-depth` (the root is depth 0)
+        let children = children_map.get(rel_path);
+        let truncated = children.is_some()
+            && args.export_depth.is_some_and(|max| rel_path.components().count() >= max);
         let mut children_nodes = Vec::new();
-        if let Some(children) = children_map.get(rel_path) {
+        if let Some(children) = children.filter(|_| !truncated) {
             for child_rel in children {
-                let child_node = build_node(child_rel, root_path, children_map, args);
+                let child_node = build_node(
+                    child_rel,
+                    root_path,
+                    children_map,
+                    infos,
+                    counts,
+                    relative_to,
+                    args,
+                );
                 if args.dirs_only && !child_node.is_dir {
                     continue;
                 }
@@ -207,6 +557,7 @@ fn build_export_tree(tree: &Tree, args: &Args) -> ExportNode {
             }
         }
 
+        let (dir_count, file_count) = node_counts(counts, &full_path, is_dir);
         ExportNode {
             name: if rel_path.as_os_str().is_empty() {
                 root_path.file_name().unwrap_or_default().to_string_lossy().to_string()
@@ -215,16 +566,129 @@ fn build_export_tree(tree: &Tree, args: &Args) -> ExportNode {
             },
             path: display_path,
             is_dir,
-            size,
-            dir_count: None,
-            file_count: None,
+            size: info.and_then(|info| info.size),
+            dir_count: Some(dir_count),
+            file_count: Some(file_count),
             permissions,
             children: if children_nodes.is_empty() { None } else { Some(children_nodes) },
+            by_extension: None,
+            scan: None,
+            truncated,
         }
     }
 
     // ────────────────────────────────
     // Explicitly build the root node
     // ────────────────────────────────
-    build_node(Path::new(""), root_path, &children_map, args)
+    // The root carries the recursive totals of the whole export
+    let mut root = build_node(
+        Path::new(""),
+        root_path,
+        &children_map,
+        &infos,
+        &counts,
+        relative_to.as_ref(),
+        args,
+    );
+    root.size = Some(tree.totals().2);
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_extension_summary() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("main.rs"), "fn").unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.RS"), "mod").unwrap();
+        fs::write(dir.path().join("README.md"), "hello").unwrap();
+
+        let args = Args::parse_from(["wisu".as_ref(), dir.path().as_os_str()]);
+        let tree = Tree::prepare(&args, false).unwrap();
+        let summary = build_extension_summary(&tree);
+
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary["rs"], ExtensionStats { count: 2, total_size: 5 });
+        assert_eq!(summary["md"], ExtensionStats { count: 1, total_size: 5 });
+    }
+
+    #[test]
+    fn test_export_tree_counts() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        fs::write(dir.path().join("a/b/deep.txt"), "1234").unwrap();
+        fs::write(dir.path().join("a/mid.txt"), "12").unwrap();
+        fs::write(dir.path().join("top.txt"), "1").unwrap();
+
+        let args = Args::parse_from(["wisu".as_ref(), dir.path().as_os_str()]);
+        let tree = Tree::prepare(&args, false).unwrap();
+        let root = build_export_tree(&tree, &args);
+
+        assert_eq!((root.dir_count, root.file_count, root.size), (Some(2), Some(3), Some(7)));
+        let a = root.children.as_ref().unwrap().iter().find(|n| n.name == "a").unwrap();
+        assert_eq!((a.dir_count, a.file_count, a.size), (Some(1), Some(2), Some(6)));
+    }
+
+    #[test]
+    fn test_export_counts_follow_filters() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        for name in ["1.txt", "2.txt", "3.txt", "4.rs", "5.rs"] {
+            fs::write(dir.path().join("a").join(name), "x").unwrap();
+        }
+        fs::write(dir.path().join("a/b/deep.rs"), "x").unwrap();
+        let counts = |extra: &[&str]| {
+            let argv = ["wisu", &dir.path().to_string_lossy()].map(String::from);
+            let args =
+                Args::parse_from(argv.into_iter().chain(extra.iter().map(|s| s.to_string())));
+            let tree = Tree::prepare(&args, false).unwrap();
+            let root = build_export_tree(&tree, &args);
+            let a = root.children.as_ref().unwrap().iter().find(|n| n.name == "a").unwrap();
+            let flat = build_export_flat_list(&tree, &args).unwrap();
+            let flat_a = flat.iter().find(|n| n.name == "a").unwrap();
+            assert_eq!((flat_a.dir_count, flat_a.file_count), (a.dir_count, a.file_count));
+            ((root.dir_count, root.file_count), (a.dir_count, a.file_count))
+        };
+
+        // Two files per directory: `a` lists 2 of its 5, `b` its one
+        assert_eq!(counts(&["-F", "2"]), ((Some(2), Some(3)), (Some(1), Some(3))));
+        assert_eq!(counts(&["--only-ext", "rs"]), ((Some(2), Some(3)), (Some(1), Some(3))));
+    }
+
+    #[test]
+    fn test_export_depth_truncates() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b/c")).unwrap();
+        fs::write(dir.path().join("a/b/c/deep.txt"), "x").unwrap();
+        fs::write(dir.path().join("a/top.txt"), "x").unwrap();
+
+        let args = Args::parse_from([
+            "wisu".as_ref(),
+            dir.path().as_os_str(),
+            "--export-depth".as_ref(),
+            "2".as_ref(),
+        ]);
+        let tree = Tree::prepare(&args, false).unwrap();
+        let root = build_export_tree(&tree, &args);
+
+        let a = &root.children.as_ref().unwrap()[0];
+        assert!(!a.truncated);
+        let b = a.children.as_ref().unwrap().iter().find(|n| n.name == "b").unwrap();
+        assert!(b.truncated);
+        assert!(b.children.is_none());
+        // Counts still cover the omitted subtree
+        assert_eq!(root.file_count, Some(2));
+    }
+
+    #[test]
+    fn test_scan_stats() {
+        let stats = ScanStats::new(500, std::time::Duration::from_millis(250));
+        assert_eq!(stats, ScanStats { entries: 500, duration_ms: 250.0, entries_per_sec: 2000.0 });
+        assert_eq!(ScanStats::new(3, std::time::Duration::ZERO).entries_per_sec, 0.0);
+    }
 }
@@ -1,5 +1,7 @@
+pub mod checksum;
 pub mod export;
 pub mod tui;
 pub(crate) mod view;
+pub mod watch;
 
 pub use export::export;
@@ -1,18 +1,19 @@
-use crate::app::Args;
-use crate::common::tree::{Tree, TreeEntry, TreeWatcher};
-use crate::utils::dir::canonicalize_path;
-use crate::utils::format;
+use crate::app::{Args, KeyAction, SearchMatcher};
+use crate::common::tree::{Tree, TreeEntry, TreeWatcher, WATCH_DEBOUNCE};
+use crate::utils::dir::{canonicalize_path, display_path};
+use anyhow::Context;
+use globset::{GlobBuilder, GlobMatcher};
 use lscolors::{Color as LsColor, LsColors, Style as LsStyle};
 use ratatui::crossterm::event::{
-    DisableMouseCapture, EnableMouseCapture, KeyEventKind, MouseEventKind,
+    DisableMouseCapture, EnableMouseCapture, KeyEventKind, MouseButton, MouseEventKind,
 };
 use ratatui::crossterm::{
-    event::{self, Event, KeyCode, KeyModifiers},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
-    backend::{Backend, CrosstermBackend}, layout::{Constraint, Direction, Layout},
+    backend::{Backend, CrosstermBackend}, buffer::Buffer, layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
@@ -20,18 +21,44 @@ use ratatui::{
     Terminal,
 };
 use regex::Regex;
-use std::collections::HashSet;
-use std::io::{stdout, Stdout};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{stdout, Read, Stdout};
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
 use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthStr;
+use url::Url;
 
-/// TUI modes: normal navigation vs search mode
-#[derive(Clone, Copy, PartialEq)]
+/// Marks the selected row of the list
+const HIGHLIGHT_SYMBOL: &str = "→ ";
+
+/// Bytes of a file read for the preview pane
+const PREVIEW_BYTES: usize = 16 * 1024;
+
+/// Bytes of a binary file shown as a hexdump in the preview pane
+const PREVIEW_HEX_BYTES: usize = 256;
+
+/// TUI modes: normal navigation, search, or a yes/no prompt in the status bar
+#[derive(Clone, PartialEq)]
 enum Mode {
     Normal,
     Search,
+    Confirm(PendingAction),
+}
+
+/// View state kept across a refresh
+struct TuiState {
+    expanded: HashSet<PathBuf>,
+    selected: Option<PathBuf>,
+}
+
+/// Action waiting for confirmation
+#[derive(Clone, PartialEq)]
+enum PendingAction {
+    /// Delete the entry at this path (with its contents); a path survives a refresh, an index does not
+    Delete(PathBuf),
 }
 
 /// Wrapper around TreeEntry to store expansion state for directories
@@ -39,6 +66,8 @@ enum Mode {
 struct TuiEntry {
     data: TreeEntry,
     expanded: bool,
+    // Number of files hidden behind a "… N more" entry
+    more: Option<usize>,
 }
 
 /// Represents what to do when exiting the TUI
@@ -47,6 +76,19 @@ enum ExitAction {
     PrintPath(PathBuf),
 }
 
+/// What the main loop does with a terminal event, see `handle_event`
+#[derive(Debug, PartialEq)]
+enum EventAction {
+    /// A key press for the key bindings
+    Key(KeyEvent),
+    /// The terminal was resized: clear it before the next draw
+    Resized,
+    /// Fully handled (mouse scrolling and clicks)
+    Handled,
+    /// Nothing to do (key releases, focus, paste, other mouse events)
+    Ignored,
+}
+
 /// Result of checking filesystem changes
 enum ChangeResult {
     /// No changes detected
@@ -59,16 +101,36 @@ enum ChangeResult {
 
 /// Main TUI application state
 pub struct TuiApp {
-    // All entries in the current view
+    // All entries in the current view; the ".." entry, if any, is always last
     entries: Vec<TuiEntry>,
+    // Parent directory → indices of its direct children in `entries`
+    children_index: HashMap<PathBuf, Vec<usize>>,
+    // Maximum number of files listed per directory (`-F`)
+    file_cap: Option<usize>,
+    // Directories whose "… N more" entry was selected
+    uncapped_dirs: HashSet<PathBuf>,
+    // "… N more" entries, addressed after `entries` in `filtered_indices`
+    more_entries: Vec<TuiEntry>,
+    // Width of the info column, recomputed when the visible entries change
+    info_width: Option<usize>,
     // Indices of currently visible entries
     filtered_indices: Vec<usize>,
     // Tracks selection in the list
     list_state: ListState,
+    // Rows of the list area inside its borders, captured on render (for page scrolling)
+    viewport_rows: usize,
+    // Area of the bordered list, captured on render (for mouse clicks)
+    list_area: Rect,
+    // Whether the preview pane is shown (toggled with `p`)
+    preview: bool,
+    // Preview lines of the last previewed path, rebuilt when the selection moves
+    preview_cache: Option<(PathBuf, Vec<String>)>,
     // Current mode (Normal/Search)
     mode: Mode,
     // Search query string
     search_query: String,
+    // How the query matches names (regex queries excepted)
+    search_matcher: SearchMatcher,
     // Backup of indices before search
     backup_indices: Vec<usize>,
     // Currently displayed directory
@@ -79,6 +141,8 @@ pub struct TuiApp {
     last_change_detected: Option<Instant>,
     pending_changed_paths: HashSet<PathBuf>,
     watch_status: Option<String>,
+    // Opened on the first copy and kept open: on X11 the copied text is served by this process
+    clipboard: Option<arboard::Clipboard>,
 }
 
 impl TuiApp {
@@ -88,15 +152,27 @@ impl TuiApp {
         watcher: Option<TreeWatcher>,
     ) -> Self {
         let current_dir = current_dir.into();
-        let entries: Vec<TuiEntry> =
-            entries.into_iter().map(|e| TuiEntry { data: e, expanded: false }).collect();
+        let entries: Vec<TuiEntry> = entries
+            .into_iter()
+            .map(|e| TuiEntry { data: e, expanded: false, more: None })
+            .collect();
 
         let mut app = Self {
             entries,
+            children_index: HashMap::new(),
+            file_cap: None,
+            uncapped_dirs: HashSet::new(),
+            more_entries: Vec::new(),
+            info_width: None,
             filtered_indices: Vec::new(),
             list_state: ListState::default(),
+            viewport_rows: 0,
+            list_area: Rect::default(),
+            preview: false,
+            preview_cache: None,
             mode: Mode::Normal,
             search_query: String::new(),
+            search_matcher: SearchMatcher::default(),
             backup_indices: Vec::new(),
             current_dir: current_dir.clone(),
             root_dir: current_dir, // <- qui impostiamo il root
@@ -104,11 +180,146 @@ impl TuiApp {
             last_change_detected: None,
             pending_changed_paths: HashSet::new(),
             watch_status: None,
+            clipboard: None,
         };
+        app.rebuild_children_index();
         app.rebuild_visible_list();
         app
     }
 
+    /// Indexes entries by parent so entering a directory costs O(children)
+    fn rebuild_children_index(&mut self) {
+        self.children_index.clear();
+        for (idx, entry) in self.entries.iter().enumerate() {
+            if is_back_entry(entry) {
+                continue;
+            }
+            if let Some(parent) = entry.data.path.parent() {
+                self.children_index.entry(parent.to_path_buf()).or_default().push(idx);
+            }
+        }
+    }
+
+    /// Index of the ".." entry, kept at the end of `entries` so other indices never shift
+    #[inline]
+    fn back_entry_index(&self) -> Option<usize> {
+        self.entries.last().filter(|e| is_back_entry(e)).map(|_| self.entries.len() - 1)
+    }
+
+    /// Replaces the ".." entry to match `current_dir`
+    fn update_back_entry(&mut self) {
+        if self.back_entry_index().is_some() {
+            self.entries.pop();
+        }
+
+        // Only add ".." when below the base root
+        if self.current_dir != self.root_dir
+            && let Some(parent) = self.current_dir.parent()
+        {
+            self.entries.push(TuiEntry {
+                data: TreeEntry {
+                    path: parent.to_path_buf(),
+                    depth: 0,
+                    is_directory: true,
+                    size: None,
+                    files: None,
+                    dirs: None,
+                    icon: Some("..".to_string()),
+                    permissions: None,
+                    owner: None,
+                    modified: None,
+                    connector: String::new(),
+                    is_executable: false,
+                    collapsed: None,
+                    tags: Vec::new(),
+                    in_archive: false,
+                },
+                expanded: false,
+                more: None,
+            });
+        }
+    }
+
+    /// Entry at `idx` of `filtered_indices`, including "… N more" entries
+    #[inline]
+    fn entry(&self, idx: usize) -> &TuiEntry {
+        self.entries.get(idx).unwrap_or_else(|| &self.more_entries[idx - self.entries.len()])
+    }
+
+    /// Caps the files listed per directory, like `-F` in the classic view
+    pub fn set_file_cap(&mut self, cap: Option<usize>) {
+        self.file_cap = cap;
+        self.rebuild_visible_list();
+    }
+
+    /// Replaces visible files beyond the cap with a "… N more" entry per directory
+    fn apply_file_cap(&mut self) {
+        self.info_width = None;
+        self.more_entries.clear();
+        let Some(cap) = self.file_cap else { return };
+
+        let base = self.entries.len();
+        let mut shown: HashMap<&Path, usize> = HashMap::new();
+        let mut more_of: HashMap<&Path, usize> = HashMap::new();
+        let mut capped = Vec::with_capacity(self.filtered_indices.len());
+
+        for &idx in &self.filtered_indices {
+            let entry = &self.entries[idx];
+            let parent = match entry.data.path.parent() {
+                Some(parent) if !entry.data.is_directory && !is_back_entry(entry) => parent,
+                _ => {
+                    capped.push(idx);
+                    continue;
+                }
+            };
+            if self.uncapped_dirs.contains(parent) {
+                capped.push(idx);
+                continue;
+            }
+
+            let count = shown.entry(parent).or_default();
+            if *count < cap {
+                *count += 1;
+                capped.push(idx);
+                continue;
+            }
+
+            match more_of.get(parent) {
+                Some(&m) => *self.more_entries[m].more.get_or_insert(0) += 1,
+                None => {
+                    more_of.insert(parent, self.more_entries.len());
+                    capped.push(base + self.more_entries.len());
+                    self.more_entries.push(TuiEntry {
+                        data: TreeEntry {
+                            path: parent.to_path_buf(),
+                            depth: entry.data.depth,
+                            ..Default::default()
+                        },
+                        expanded: false,
+                        more: Some(1),
+                    });
+                }
+            }
+        }
+
+        self.filtered_indices = capped;
+    }
+
+    /// Reveals the files behind a "… N more" entry; returns false for other entries
+    pub fn expand_more(&mut self, entry_idx: usize) -> bool {
+        let entry = self.entry(entry_idx);
+        if entry.more.is_none() {
+            return false;
+        }
+
+        let dir = entry.data.path.clone();
+        let selected = self.list_state.selected();
+        self.uncapped_dirs.insert(dir);
+        self.rebuild_visible_list();
+        self.list_state.select(selected);
+        true
+    }
+
     /// Check for filesystem changes and determine what kind of update is needed
     fn check_for_changes(&mut self) -> ChangeResult {
         let Some(watcher) = &self.watcher else { return ChangeResult::None };
@@ -133,10 +344,10 @@ impl TuiApp {
         }
 
         // Still debouncing
-        if let Some(detected_at) = self.last_change_detected {
-            if detected_at.elapsed() < Duration::from_millis(300) {
-                return ChangeResult::Pending;
-            }
+        if let Some(detected_at) = self.last_change_detected
+            && detected_at.elapsed() < WATCH_DEBOUNCE
+        {
+            return ChangeResult::Pending;
         }
 
         // Debounce complete, trigger refresh
@@ -149,35 +360,102 @@ impl TuiApp {
 
     /// Refresh the tree entries while preserving state
     pub fn refresh_entries(&mut self, new_entries: Vec<TreeEntry>) {
-        // Store current selection path
-        let selected_path = self.get_current_entry().map(|e| e.data.path.clone());
+        let state = self.snapshot_state();
 
-        // Store expanded directories
-        let expanded_paths: HashSet<PathBuf> =
-            self.entries.iter().filter(|e| e.expanded).map(|e| e.data.path.clone()).collect();
-
-        // Update entries
         self.entries = new_entries
             .into_iter()
-            .map(|e| {
-                let was_expanded = expanded_paths.contains(&e.path);
-                TuiEntry { data: e, expanded: was_expanded }
-            })
+            .map(|e| TuiEntry { data: e, expanded: false, more: None })
             .collect();
+        self.rebuild_children_index();
+        self.update_back_entry();
+        self.restore_state(state);
+        self.preview_cache = None;
 
-        // Rebuild visible list
+        self.watch_status = Some("Updated ✓".to_string());
+    }
+
+    /// Expanded directories and selected path, to carry across a rescan
+    fn snapshot_state(&self) -> TuiState {
+        TuiState {
+            expanded: self
+                .entries
+                .iter()
+                .filter(|e| e.expanded)
+                .map(|e| e.data.path.clone())
+                .collect(),
+            selected: self.get_current_entry().map(|e| e.data.path.clone()),
+        }
+    }
+
+    /// Re-expands the directories of `state` and selects its path again if it still exists
+    fn restore_state(&mut self, state: TuiState) {
+        for entry in &mut self.entries {
+            entry.expanded = state.expanded.contains(&entry.data.path);
+        }
         self.rebuild_visible_list();
 
-        // Restore selection
-        if let Some(path) = selected_path {
-            if let Some(pos) =
-                self.filtered_indices.iter().position(|&i| self.entries[i].data.path == path)
-            {
-                self.list_state.select(Some(pos));
-            }
+        if let Some(path) = state.selected
+            && let Some(pos) =
+                self.filtered_indices.iter().position(|&i| self.entry(i).data.path == path)
+        {
+            self.list_state.select(Some(pos));
         }
+    }
 
-        self.watch_status = Some("Updated ✓".to_string());
+    /// Asks to confirm deleting the selected entry; ".." and the root are never deleted
+    fn request_delete(&mut self) {
+        let Some(&idx) = self.list_state.selected().and_then(|i| self.filtered_indices.get(i))
+        else {
+            return;
+        };
+        let Some(entry) = self.entries.get(idx) else { return };
+        if is_back_entry(entry) || self.is_protected(&entry.data.path) {
+            return;
+        }
+        self.mode = Mode::Confirm(PendingAction::Delete(entry.data.path.clone()));
+    }
+
+    /// The root and the directories leading to the current one, which are never deleted
+    fn is_protected(&self, path: &Path) -> bool {
+        self.root_dir.starts_with(path) || self.current_dir.starts_with(path)
+    }
+
+    /// Deletes the entry at `path` from disk, then drops it and its subtree from the tree.
+    ///
+    /// The entry is looked up again, since a watch refresh may have changed the tree
+    /// while the confirmation prompt was open.
+    fn delete_entry(&mut self, path: &Path) -> anyhow::Result<PathBuf> {
+        if self.is_protected(path) {
+            anyhow::bail!("Refusing to delete {}", path.display());
+        }
+        let Some(entry) = self.entries.iter().find(|e| !is_back_entry(e) && e.data.path == path)
+        else {
+            anyhow::bail!("{} is no longer in the tree", path.display());
+        };
+        let path = entry.data.path.clone();
+        if entry.data.is_directory { fs::remove_dir_all(&path) } else { fs::remove_file(&path) }
+            .with_context(|| format!("Failed to delete {}", path.display()))?;
+
+        let selected = self.list_state.selected().unwrap_or(0);
+        self.entries.retain(|e| is_back_entry(e) || !e.data.path.starts_with(&path));
+        self.rebuild_children_index();
+        self.rebuild_visible_list();
+        self.list_state
+            .select(self.filtered_indices.len().checked_sub(1).map(|last| selected.min(last)));
+        Ok(path)
+    }
+
+    /// Copies the canonical path of `path` to the system clipboard, returning the copied text
+    fn copy_to_clipboard(&mut self, path: &Path) -> anyhow::Result<String> {
+        let text = clipboard_text(path);
+        let clipboard = match &mut self.clipboard {
+            Some(clipboard) => clipboard,
+            None => {
+                self.clipboard.insert(arboard::Clipboard::new().context("Clipboard unavailable")?)
+            }
+        };
+        clipboard.set_text(text.as_str()).context("Failed to copy to the clipboard")?;
+        Ok(text)
     }
 
     /// Clear the watch status message
@@ -185,54 +463,77 @@ impl TuiApp {
         self.watch_status = None;
     }
 
-    pub fn apply_initial_expansion(&mut self, expand_level: Option<usize>) {
+    pub fn apply_initial_expansion(&mut self, expand_level: Option<usize>, rules: &ExpandRules) {
+        if expand_level.is_none() && rules.is_empty() {
+            return;
+        }
+
         if let Some(level) = expand_level {
             for entry in &mut self.entries {
                 if entry.data.is_directory && entry.data.depth < level {
                     entry.expanded = true;
                 }
             }
-            self.rebuild_visible_list();
         }
+
+        // Per-directory rules override the global level; the innermost matching rule wins
+        let mut active: Vec<(PathBuf, usize, usize)> = Vec::new();
+        for entry in &mut self.entries {
+            if !entry.data.is_directory || is_back_entry(entry) {
+                continue;
+            }
+            while active.last().is_some_and(|(dir, _, _)| !entry.data.path.starts_with(dir)) {
+                active.pop();
+            }
+
+            let rel = entry.data.path.strip_prefix(&self.root_dir).unwrap_or(&entry.data.path);
+            if let Some(level) = rules.level_for(rel) {
+                active.push((entry.data.path.clone(), entry.data.depth, level));
+            }
+            if let Some(&(_, depth, level)) = active.last() {
+                entry.expanded = entry.data.depth - depth < level;
+            }
+        }
+
+        self.rebuild_visible_list();
     }
 
-    /// Ricostruisce la lista dei nodi visibili in base alla directory corrente
+    /// Rebuilds the list of visible entries for the current directory
     pub fn rebuild_visible_list(&mut self) {
         self.filtered_indices.clear();
-        let mut parent_expanded_stack = Vec::with_capacity(16);
 
+        if self.current_dir != self.root_dir {
+            // Subdir: ".." followed by the direct children of current_dir
+            self.filtered_indices.extend(self.back_entry_index());
+            if let Some(children) = self.children_index.get(&self.current_dir) {
+                self.filtered_indices.extend(children);
+            }
+            self.apply_file_cap();
+            self.list_state.select(Some(0));
+            return;
+        }
+
+        // Full tree honoring expansions
+        let mut parent_expanded_stack = Vec::with_capacity(16);
         for (idx, entry) in self.entries.iter().enumerate() {
-            // ".." è sempre visibile se non siamo alla root
-            if entry.data.icon.as_deref() == Some("..") {
+            let target_depth = entry.data.depth.saturating_sub(1);
+            parent_expanded_stack.truncate(target_depth);
+            let visible = entry.data.depth == 0 || parent_expanded_stack.iter().all(|&e| e);
+            if visible {
                 self.filtered_indices.push(idx);
-                continue;
             }
-
-            if self.current_dir == self.root_dir {
-                // Logica albero completo con espansioni
-                let target_depth = entry.data.depth.saturating_sub(1);
-                parent_expanded_stack.truncate(target_depth);
-                let visible = entry.data.depth == 0 || parent_expanded_stack.iter().all(|&e| e);
-                if visible {
-                    self.filtered_indices.push(idx);
-                }
-                if entry.data.is_directory && entry.data.depth > 0 {
-                    parent_expanded_stack.push(entry.expanded);
-                }
-            } else {
-                // Subdir: mostra solo figli diretti della current_dir
-                if entry.data.path.parent().map(|p| p == self.current_dir).unwrap_or(false) {
-                    self.filtered_indices.push(idx);
-                }
+            if entry.data.is_directory && entry.data.depth > 0 {
+                parent_expanded_stack.push(entry.expanded);
             }
         }
+        self.apply_file_cap();
         self.list_state.select(Some(0));
     }
 
     pub fn toggle_expansion(&mut self) {
         let Some(sel_idx) = self.list_state.selected() else { return };
         let Some(&entry_idx) = self.filtered_indices.get(sel_idx) else { return };
-        if !self.entries[entry_idx].data.is_directory {
+        if !self.entries.get(entry_idx).is_some_and(|e| e.data.is_directory) {
             return;
         }
 
@@ -243,11 +544,30 @@ impl TuiApp {
         let new_pos = self
             .filtered_indices
             .iter()
-            .position(|&i| self.entries[i].data.path == path)
+            .position(|&i| self.entry(i).data.path == path)
             .unwrap_or_else(|| sel_idx.min(self.filtered_indices.len().saturating_sub(1)));
         self.list_state.select(Some(new_pos));
     }
 
+    /// Expands or collapses every directory, keeping the selection on the same path
+    /// (or on its closest visible ancestor once collapsed)
+    fn set_all_expanded(&mut self, expanded: bool) {
+        let selected = self.get_current_entry().map(|e| e.data.path.clone());
+
+        for entry in &mut self.entries {
+            if entry.data.is_directory && !is_back_entry(entry) {
+                entry.expanded = expanded;
+            }
+        }
+        self.rebuild_visible_list();
+
+        let Some(path) = selected else { return };
+        let position = path.ancestors().find_map(|ancestor| {
+            self.filtered_indices.iter().position(|&i| self.entry(i).data.path == ancestor)
+        });
+        self.list_state.select(Some(position.unwrap_or(0)));
+    }
+
     #[inline]
     fn move_selection_down(&mut self) {
         if self.filtered_indices.is_empty() {
@@ -274,6 +594,55 @@ impl TuiApp {
         self.list_state.select(Some(prev));
     }
 
+    /// Moves the selection by `delta` rows, stopping at the first and last entry
+    fn move_selection_by(&mut self, delta: isize) {
+        let Some(last) = self.filtered_indices.len().checked_sub(1) else {
+            return;
+        };
+        let current = self.list_state.selected().unwrap_or(0);
+        self.list_state.select(Some(current.saturating_add_signed(delta).min(last)));
+    }
+
+    /// Position in `filtered_indices` of the list row drawn at screen row `y`, if any
+    fn row_to_index(&self, y: u16) -> Option<usize> {
+        let inner = Block::default().borders(Borders::ALL).inner(self.list_area);
+        if !(inner.top()..inner.bottom()).contains(&y) {
+            return None;
+        }
+        let index = self.list_state.offset() + (y - inner.top()) as usize;
+        (index < self.filtered_indices.len()).then_some(index)
+    }
+
+    /// Selects the row clicked at (`x`, `y`); clicking the selected row toggles its
+    /// expansion, outside a search or confirmation only
+    fn click(&mut self, x: u16, y: u16) {
+        if !(self.list_area.left()..self.list_area.right()).contains(&x) {
+            return;
+        }
+        let Some(index) = self.row_to_index(y) else { return };
+        if self.list_state.selected() == Some(index) && self.mode == Mode::Normal {
+            self.toggle_expansion();
+        } else {
+            self.list_state.select(Some(index));
+        }
+    }
+
+    /// Rows moved by a page (`full`) or half-page scroll, at least one
+    fn page_rows(&self, full: bool) -> isize {
+        let rows = if full { self.viewport_rows } else { self.viewport_rows / 2 };
+        rows.max(1) as isize
+    }
+
+    #[inline]
+    fn select_first(&mut self) {
+        self.list_state.select(if self.filtered_indices.is_empty() { None } else { Some(0) });
+    }
+
+    #[inline]
+    fn select_last(&mut self) {
+        self.list_state.select(self.filtered_indices.len().checked_sub(1));
+    }
+
     fn start_search(&mut self) {
         if self.mode == Mode::Normal {
             self.backup_indices = self.filtered_indices.clone();
@@ -288,18 +657,19 @@ impl TuiApp {
         }
         std::mem::swap(&mut self.filtered_indices, &mut self.backup_indices);
         self.backup_indices.clear();
+        self.info_width = None;
         self.mode = Mode::Normal;
         self.search_query.clear();
 
-        if let Some(sel) = self.list_state.selected() {
-            if sel >= self.filtered_indices.len() {
-                let new_sel = if self.filtered_indices.is_empty() {
-                    None
-                } else {
-                    Some(self.filtered_indices.len() - 1)
-                };
-                self.list_state.select(new_sel);
-            }
+        if let Some(sel) = self.list_state.selected()
+            && sel >= self.filtered_indices.len()
+        {
+            let new_sel = if self.filtered_indices.is_empty() {
+                None
+            } else {
+                Some(self.filtered_indices.len() - 1)
+            };
+            self.list_state.select(new_sel);
         }
     }
 
@@ -311,20 +681,19 @@ impl TuiApp {
             return;
         }
 
-        let (is_regex, query) =
-            if raw_query.starts_with("r:") { (true, &raw_query[2..]) } else { (false, raw_query) };
+        let (is_regex, query) = match raw_query.strip_prefix("r:") {
+            Some(pattern) => (true, pattern),
+            None => (false, raw_query),
+        };
 
         self.filtered_indices.clear();
 
         // Only consider direct children of current_dir, exclude ".." and current_dir itself
         let visible_entries: Vec<(usize, &TuiEntry)> = self
-            .entries
-            .iter()
-            .enumerate()
-            .filter(|(_, entry)| {
-                entry.data.path.parent().map(|p| p == self.current_dir).unwrap_or(false)
-            })
-            .collect();
+            .children_index
+            .get(&self.current_dir)
+            .map(|children| children.iter().map(|&idx| (idx, &self.entries[idx])).collect())
+            .unwrap_or_default();
 
         if is_regex {
             if let Ok(re) = Regex::new(query) {
@@ -343,79 +712,107 @@ impl TuiApp {
             // else invalid regex → empty results
         } else {
             let query_lc = query.to_lowercase();
-            for (idx, entry) in visible_entries {
-                let name = entry
-                    .data
-                    .path
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_lowercase())
-                    .unwrap_or_default();
-                if name.contains(&query_lc) {
-                    self.filtered_indices.push(idx);
-                }
-            }
+            let mut scored: Vec<(usize, i64)> = visible_entries
+                .into_iter()
+                .filter_map(|(idx, entry)| {
+                    let name = entry
+                        .data
+                        .path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_lowercase())
+                        .unwrap_or_default();
+                    self.search_matcher.find(&name, &query_lc).map(|(score, _)| (idx, score))
+                })
+                .collect();
+
+            // Stable, so equal scores (every substring/prefix match) keep the tree order
+            scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+            self.filtered_indices.extend(scored.into_iter().map(|(idx, _)| idx));
         }
 
+        self.info_width = None;
         self.list_state.select(if self.filtered_indices.is_empty() { None } else { Some(0) });
     }
 
+    /// Hyperlinks the file names of the rendered rows of the list drawn in `area`
+    fn link_visible_names(&self, buf: &mut Buffer, area: Rect, columns: &[Option<(u16, u16)>]) {
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        let symbol_width =
+            if self.list_state.selected().is_some() { HIGHLIGHT_SYMBOL.width() as u16 } else { 0 };
+        let offset = self.list_state.offset();
+
+        let rows = columns.iter().zip(&self.filtered_indices).skip(offset);
+        for (y, (column, &idx)) in (inner.top()..inner.bottom()).zip(rows) {
+            let Some((column, width)) = *column else { continue };
+            let x = inner.x + symbol_width + column;
+            let Some(url) = file_url(&self.entry(idx).data.path) else { continue };
+            if x < inner.right() {
+                link_cells(buf, x, y, width.min(inner.right() - x), &url);
+            }
+        }
+    }
+
+    /// Char indices of `name` matched by the current (non-regex) search query
+    fn search_highlight(&self, name: &str) -> Option<Vec<usize>> {
+        let query = self.search_query.trim();
+        if self.mode != Mode::Search || query.is_empty() || query.starts_with("r:") {
+            return None;
+        }
+
+        self.search_matcher
+            .find(&name.to_lowercase(), &query.to_lowercase())
+            .map(|(_, positions)| positions)
+    }
+
     #[inline]
     fn get_current_entry(&self) -> Option<&TuiEntry> {
         self.list_state
             .selected()
             .and_then(|i| self.filtered_indices.get(i))
-            .and_then(|&idx| self.entries.get(idx))
+            .map(|&idx| self.entry(idx))
     }
 
     pub fn enter_directory(&mut self, entry_idx: usize) {
-        let entry = &self.entries[entry_idx];
+        let entry = self.entry(entry_idx);
         if !entry.data.is_directory {
             return;
         }
 
         self.current_dir = entry.data.path.clone();
-
-        // Rimuovi eventuale ".." precedente
-        self.entries.retain(|e| e.data.icon.as_deref() != Some(".."));
-
-        // Aggiungi ".." solo se non siamo nella root base
-        if self.current_dir != self.root_dir {
-            if let Some(parent) = self.current_dir.parent() {
-                let back_entry = TuiEntry {
-                    data: TreeEntry {
-                        path: parent.to_path_buf(),
-                        depth: 0,
-                        is_directory: true,
-                        size: None,
-                        files: None,
-                        dirs: None,
-                        icon: Some("..".to_string()),
-                        permissions: None,
-                        connector: String::new(),
-                    },
-                    expanded: false,
-                };
-                self.entries.insert(0, back_entry);
-            }
-        }
-
+        self.update_back_entry();
         self.rebuild_visible_list();
     }
 
     pub fn go_up(&mut self) {
-        // Se siamo nella root base, non salire sopra
+        // Never go above the base root
         if self.current_dir == self.root_dir {
             return;
         }
 
-        // Trova l'indice del nodo ".." e entra nella directory superiore
-        if let Some(parent) = self.current_dir.parent() {
-            if let Some(back_idx) = self.entries.iter().position(|e| e.data.path == parent) {
-                self.enter_directory(back_idx);
-            }
+        // The ".." entry points at the parent directory
+        if let Some(back_idx) = self.back_entry_index() {
+            self.enter_directory(back_idx);
         }
     }
 
+    /// Renders the preview of the selected entry into `area`
+    fn render_preview(&mut self, f: &mut Frame, area: Rect) {
+        let path = self.get_current_entry().map(|e| e.data.path.clone());
+        let lines = match (&path, &self.preview_cache) {
+            (None, _) => Vec::new(),
+            (Some(path), Some((cached, lines))) if cached == path => lines.clone(),
+            (Some(path), _) => {
+                let lines = preview_lines(path, PREVIEW_BYTES);
+                self.preview_cache = Some((path.clone(), lines.clone()));
+                lines
+            }
+        };
+
+        let preview = Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<_>>())
+            .block(Block::default().title("Preview").borders(Borders::ALL));
+        f.render_widget(preview, area);
+    }
+
     /// Render the TUI
     pub fn render<B: Backend>(&mut self, f: &mut Frame, args: &Args, ls_colors: &LsColors)
     where
@@ -430,30 +827,63 @@ impl TuiApp {
             ])
             .split(f.area());
 
+        // Tree on the left, preview on the right when enabled
+        let list_area = if self.preview {
+            let panes = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(chunks[1]);
+            self.render_preview(f, panes[1]);
+            panes[0]
+        } else {
+            chunks[1]
+        };
+        self.viewport_rows = list_area.height.saturating_sub(2) as usize;
+        self.list_area = list_area;
+
         // Breadcrumb path at the top (with watch indicator if active)
         let breadcrumb_text = if self.watcher.is_some() {
-            format!("watching: {}", self.current_dir.display())
+            format!("watching: {}", display_path(&self.current_dir))
         } else {
-            self.current_dir.display().to_string()
+            display_path(&self.current_dir)
         };
         let breadcrumb = Paragraph::new(breadcrumb_text)
             .style(Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC));
         f.render_widget(breadcrumb, chunks[0]);
 
-        // Prepare list items
+        // Prepare list items, with the (column, width) of each file name to hyperlink
         let mut list_items = Vec::with_capacity(self.filtered_indices.len());
+        let mut link_columns = Vec::with_capacity(self.filtered_indices.len());
+        let info_width = match self.info_width {
+            Some(width) => width,
+            None => {
+                let visible = self.filtered_indices.iter().map(|&idx| &self.entry(idx).data);
+                *self.info_width.insert(info_column_width(visible, args))
+            }
+        };
 
         for &idx in &self.filtered_indices {
-            let entry = &self.entries[idx];
+            let entry = self.entry(idx);
             let mut spans = Vec::with_capacity(6);
 
-            if args.permissions {
-                if let Some(perm) = &entry.data.permissions {
-                    spans.push(Span::styled(
-                        format!("{perm} "),
-                        Style::default().fg(Color::DarkGray),
-                    ));
-                }
+            if let Some(hidden) = entry.more {
+                spans.push(Span::raw("    ".repeat(entry.data.depth)));
+                spans.push(Span::styled(
+                    format!("  … {hidden} more"),
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ));
+                list_items.push(ListItem::new(Line::from(spans)));
+                link_columns.push(None);
+                continue;
+            }
+
+            if args.permissions
+                && let Some(perm) = &entry.data.permissions
+            {
+                spans.push(Span::styled(format!("{perm} "), Style::default().fg(Color::DarkGray)));
+            }
+            if let Some(owner) = &entry.data.owner {
+                spans.push(Span::styled(owner.clone(), Style::default().fg(Color::DarkGray)));
             }
 
             if entry.data.depth > 0 {
@@ -475,40 +905,40 @@ impl TuiApp {
 
             let style = ls_colors.style_for_path(&entry.data.path).cloned().unwrap_or_default();
 
-            spans.push(Span::styled(name.to_string(), convert_ls_style(style)));
+            let name_style = convert_ls_style(style);
 
-            // Optional info aligned to the right
-            let mut info_text = String::new();
+            let name_column = spans.iter().map(|s| s.width()).sum::<usize>() as u16;
+            link_columns.push(
+                (args.hyperlinks && !entry.data.is_directory)
+                    .then(|| (name_column, name.width() as u16)),
+            );
 
-            if args.info {
-                if entry.data.is_directory {
-                    if let (Some(size), Some(files), Some(dirs)) =
-                        (entry.data.size, entry.data.files, entry.data.dirs)
-                    {
-                        info_text =
-                            format!("[{}, {} files, {} dirs]", format::size(size), files, dirs);
-                    }
-                } else if let Some(size) = entry.data.size {
-                    info_text = format!("[{}]", format::size(size));
-                }
-            } else if args.size {
-                if let Some(size) = entry.data.size {
-                    info_text = format!("[{}]", format::size(size));
-                }
+            match self.search_highlight(&name) {
+                Some(positions) => spans.extend(highlight_matches(&name, &positions, name_style)),
+                None => spans.push(Span::styled(name.to_string(), name_style)),
+            }
+
+            if let Some(tags) = entry.data.tags_label() {
+                spans.push(Span::styled(format!(" {tags}"), Style::default().fg(Color::Yellow)));
             }
 
-            if !info_text.is_empty() {
+            // Optional info, right-aligned in a column as wide as the widest visible info
+            let info = info_text(&entry.data, args);
+
+            if !info.is_empty() {
                 let used_width: usize = spans.iter().map(|s| s.width()).sum();
-                let padding = chunks[1]
-                    .width
-                    .saturating_sub(used_width as u16)
-                    .saturating_sub(info_text.len() as u16)
-                    .saturating_sub(5) as usize;
+                let padding = (list_area.width as usize)
+                    .saturating_sub(used_width)
+                    .saturating_sub(info_width)
+                    .saturating_sub(INFO_MARGIN);
 
                 if padding > 0 {
                     spans.push(Span::raw(" ".repeat(padding)));
                 }
-                spans.push(Span::styled(info_text, Style::default().fg(Color::DarkGray)));
+                spans.push(Span::styled(
+                    format!("{info:>info_width$}"),
+                    Style::default().fg(Color::DarkGray),
+                ));
             }
 
             list_items.push(ListItem::new(Line::from(spans)));
@@ -519,25 +949,40 @@ impl TuiApp {
             .highlight_style(
                 Style::default().bg(Color::DarkGray).fg(Color::White).add_modifier(Modifier::BOLD),
             )
-            .highlight_symbol("→ ");
+            .highlight_symbol(HIGHLIGHT_SYMBOL);
+
+        f.render_stateful_widget(list, list_area, &mut self.list_state);
 
-        f.render_stateful_widget(list, chunks[1], &mut self.list_state);
+        if args.hyperlinks {
+            self.link_visible_names(f.buffer_mut(), list_area, &link_columns);
+        }
 
         // Status bar with instructions or search query
-        let status_text = match self.mode {
+        let status_text = match &self.mode {
             Mode::Normal => {
-                let base = "q: quit | /: search | r: refresh | Tab: enter dir | Ctrl+T: open terminal | Ctrl+S: print path";
+                let key = |action| args.keys.key(action);
+                let base = format!(
+                    "{}: quit | {}: search | {}: refresh | Tab: enter dir | {}: reveal | Ctrl+T: open terminal | Ctrl+S: print path",
+                    key(KeyAction::Quit),
+                    key(KeyAction::Search),
+                    key(KeyAction::Refresh),
+                    key(KeyAction::Reveal)
+                );
 
                 if let Some(status) = &self.watch_status {
                     Span::styled(format!("{} | {}", base, status), Style::default().fg(Color::Gray))
                 } else {
-                    Span::styled(format!("{}", base), Style::default().fg(Color::Gray))
+                    Span::styled(base, Style::default().fg(Color::Gray))
                 }
             }
             Mode::Search => Span::styled(
-                format!("/{}", self.search_query),
+                format!("/{}  [{}, Ctrl+F: switch]", self.search_query, self.search_matcher),
                 Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
             ),
+            Mode::Confirm(PendingAction::Delete(path)) => Span::styled(
+                format!("Delete {}? (y/n)", display_path(path)),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
         };
         f.render_widget(Paragraph::new(Line::from(status_text)), chunks[2]);
     }
@@ -545,7 +990,13 @@ impl TuiApp {
 
 /// Run the TUI application
 pub fn run(args: &Args, ls_colors: &LsColors) -> anyhow::Result<()> {
-    let (tree, watcher) = Tree::prepare_with_watch(args, true)?;
+    // Scan every file and cap them per directory in the view, so they can be expanded
+    let expand_rules = ExpandRules::new(&args.tui.expand)?;
+    let file_cap = if args.dirs_only { None } else { args.files };
+    let scan_args =
+        &Args { files: if file_cap.is_some() { None } else { args.files }, ..args.clone() };
+
+    let (tree, watcher) = Tree::prepare_with_watch(scan_args, true)?;
     let entries = tree.tree_info;
 
     enable_raw_mode()?;
@@ -560,16 +1011,26 @@ pub fn run(args: &Args, ls_colors: &LsColors) -> anyhow::Result<()> {
     }
 
     let mut app = TuiApp::new(entries, args.path.clone(), watcher);
-    app.apply_initial_expansion(args.expand_level);
+    app.set_file_cap(file_cap);
+    app.apply_initial_expansion(args.expand_level, &expand_rules);
+    app.search_matcher = args.tui.search.unwrap_or_default();
 
     // Track when to clear watch status message
     let mut status_clear_time: Option<Instant> = None;
 
+    // First `g` of a `gg` (jump to top) sequence
+    let mut pending_g = false;
+
     let exit_action = loop {
-        // Check for filesystem changes (watch mode)
-        match app.check_for_changes() {
+        // Check for filesystem changes (watch mode); deferred while a prompt is open
+        let changes = if matches!(app.mode, Mode::Confirm(_)) {
+            ChangeResult::None
+        } else {
+            app.check_for_changes()
+        };
+        match changes {
             ChangeResult::NeedsRefresh => {
-                let new_tree = Tree::prepare(args, false)?;
+                let new_tree = Tree::prepare(scan_args, false)?;
                 app.refresh_entries(new_tree.tree_info);
                 status_clear_time = Some(Instant::now() + Duration::from_secs(2));
             }
@@ -577,11 +1038,11 @@ pub fn run(args: &Args, ls_colors: &LsColors) -> anyhow::Result<()> {
         }
 
         // Clear status message after timeout
-        if let Some(clear_at) = status_clear_time {
-            if Instant::now() >= clear_at {
-                app.clear_watch_status();
-                status_clear_time = None;
-            }
+        if let Some(clear_at) = status_clear_time
+            && Instant::now() >= clear_at
+        {
+            app.clear_watch_status();
+            status_clear_time = None;
         }
 
         terminal.draw(|f| app.render::<CrosstermBackend<Stdout>>(f, args, ls_colors))?;
@@ -591,20 +1052,34 @@ pub fn run(args: &Args, ls_colors: &LsColors) -> anyhow::Result<()> {
             continue;
         }
 
-        let evt = event::read()?;
+        let key = match handle_event(&mut app, event::read()?) {
+            EventAction::Key(key) => key,
+            EventAction::Resized => {
+                terminal.clear()?;
+                continue;
+            }
+            EventAction::Handled | EventAction::Ignored => continue,
+        };
 
-        if let Event::Mouse(mouse) = evt {
-            match mouse.kind {
-                MouseEventKind::ScrollUp => app.move_selection_up(),
-                MouseEventKind::ScrollDown => app.move_selection_down(),
-                _ => {}
+        if let Mode::Confirm(PendingAction::Delete(path)) = &app.mode {
+            let path = path.clone();
+            app.mode = Mode::Normal;
+            if matches!(key.code, KeyCode::Char('y' | 'Y')) {
+                app.watch_status = Some(match app.delete_entry(&path) {
+                    Ok(path) => format!("Deleted {}", display_path(&path)),
+                    Err(e) => format!("{e:#}"),
+                });
+                status_clear_time = Some(Instant::now() + Duration::from_secs(3));
             }
             continue;
         }
 
-        let Event::Key(key) = evt else { continue };
-
-        if key.kind != KeyEventKind::Press {
+        if app.mode == Mode::Search
+            && key.code == KeyCode::Char('f')
+            && key.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            app.search_matcher = app.search_matcher.next();
+            app.apply_search_filter();
             continue;
         }
 
@@ -645,15 +1120,56 @@ pub fn run(args: &Args, ls_colors: &LsColors) -> anyhow::Result<()> {
             continue;
         }
 
-        match key.code {
-            KeyCode::Char('q') => break ExitAction::None,
-            KeyCode::Char('r') => {
-                terminal.clear()?;
-                let new_tree = Tree::prepare(args, false)?;
-                app.refresh_entries(new_tree.tree_info);
-                app.apply_initial_expansion(args.expand_level);
-                terminal.clear()?;
+        let jump_to_top = pending_g && key.code == KeyCode::Char('g');
+        pending_g = key.code == KeyCode::Char('g') && !jump_to_top;
+
+        let action = match key.code {
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                args.keys.action(c)
+            }
+            KeyCode::Up => Some(KeyAction::Up),
+            KeyCode::Down => Some(KeyAction::Down),
+            _ => None,
+        };
+
+        if let Some(action) = action {
+            match action {
+                KeyAction::Quit => break ExitAction::None,
+                KeyAction::Refresh => {
+                    terminal.clear()?;
+                    let new_tree = Tree::prepare(scan_args, false)?;
+                    // Keeps the expanded directories and the selection, like a watch refresh
+                    app.refresh_entries(new_tree.tree_info);
+                    status_clear_time = Some(Instant::now() + Duration::from_secs(2));
+                    terminal.clear()?;
+                }
+                KeyAction::Search => app.start_search(),
+                KeyAction::Up => app.move_selection_up(),
+                KeyAction::Down => app.move_selection_down(),
+                KeyAction::Bottom => app.select_last(),
+                KeyAction::ExpandAll => app.set_all_expanded(true),
+                KeyAction::CollapseAll => app.set_all_expanded(false),
+                KeyAction::Delete => app.request_delete(),
+                KeyAction::Preview => app.preview = !app.preview,
+                KeyAction::Copy => {
+                    if let Some(path) = app.get_current_entry().map(|e| e.data.path.clone()) {
+                        app.watch_status = Some(match app.copy_to_clipboard(&path) {
+                            Ok(text) => format!("Copied {text}"),
+                            Err(e) => format!("{e:#}"),
+                        });
+                        status_clear_time = Some(Instant::now() + Duration::from_secs(3));
+                    }
+                }
+                KeyAction::Reveal => {
+                    if let Some(entry) = app.get_current_entry() {
+                        let _ = reveal_in_file_manager(&entry.data.path);
+                    }
+                }
             }
+            continue;
+        }
+
+        match key.code {
             KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 if let Some(entry) = app.get_current_entry() {
                     break ExitAction::PrintPath(entry.data.path.clone());
@@ -662,7 +1178,10 @@ pub fn run(args: &Args, ls_colors: &LsColors) -> anyhow::Result<()> {
             KeyCode::Right | KeyCode::Left => {
                 if let Some(sel_idx) = app.list_state.selected() {
                     let entry_idx = app.filtered_indices[sel_idx];
-                    let entry = &app.entries[entry_idx];
+                    if app.expand_more(entry_idx) {
+                        continue;
+                    }
+                    let entry = app.entry(entry_idx);
 
                     if entry.data.path != app.current_dir.parent().unwrap_or(&app.current_dir) {
                         app.toggle_expansion();
@@ -674,7 +1193,10 @@ pub fn run(args: &Args, ls_colors: &LsColors) -> anyhow::Result<()> {
             KeyCode::Enter => {
                 if let Some(sel_idx) = app.list_state.selected() {
                     let entry_idx = app.filtered_indices[sel_idx];
-                    let entry = &app.entries[entry_idx];
+                    if app.expand_more(entry_idx) {
+                        continue;
+                    }
+                    let entry = app.entry(entry_idx);
 
                     if entry.data.path == app.current_dir.parent().unwrap_or(&app.current_dir) {
                         app.go_up();
@@ -688,7 +1210,10 @@ pub fn run(args: &Args, ls_colors: &LsColors) -> anyhow::Result<()> {
             KeyCode::Tab => {
                 if let Some(sel_idx) = app.list_state.selected() {
                     let entry_idx = app.filtered_indices[sel_idx];
-                    let entry = &app.entries[entry_idx];
+                    if app.expand_more(entry_idx) {
+                        continue;
+                    }
+                    let entry = app.entry(entry_idx);
 
                     if entry.data.path == app.current_dir.parent().unwrap_or(&app.current_dir) {
                         app.go_up();
@@ -724,9 +1249,15 @@ pub fn run(args: &Args, ls_colors: &LsColors) -> anyhow::Result<()> {
                     app.rebuild_visible_list();
                 }
             }
-            KeyCode::Up => app.move_selection_up(),
-            KeyCode::Down => app.move_selection_down(),
-            KeyCode::Char('/') => app.start_search(),
+            KeyCode::PageDown => app.move_selection_by(app.page_rows(true)),
+            KeyCode::PageUp => app.move_selection_by(-app.page_rows(true)),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.move_selection_by(app.page_rows(false))
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.move_selection_by(-app.page_rows(false))
+            }
+            KeyCode::Char('g') if jump_to_top => app.select_first(),
             _ => {}
         }
     };
@@ -756,6 +1287,144 @@ fn open_terminal(dir: &Path) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Handles the events that need no terminal access; key presses go back to the caller
+fn handle_event(app: &mut TuiApp, event: Event) -> EventAction {
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => EventAction::Key(key),
+        Event::Mouse(mouse) => match mouse.kind {
+            MouseEventKind::ScrollUp => {
+                app.move_selection_up();
+                EventAction::Handled
+            }
+            MouseEventKind::ScrollDown => {
+                app.move_selection_down();
+                EventAction::Handled
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                app.click(mouse.column, mouse.row);
+                EventAction::Handled
+            }
+            _ => EventAction::Ignored,
+        },
+        Event::Resize(..) => EventAction::Resized,
+        _ => EventAction::Ignored,
+    }
+}
+
+/// Lines of the preview pane for `path`: the children of a directory, the first
+/// `max_bytes` of a text file with line numbers, or a hexdump of a binary file
+fn preview_lines(path: &Path, max_bytes: usize) -> Vec<String> {
+    if path.is_dir() {
+        return match fs::read_dir(path) {
+            Ok(children) => {
+                let mut names: Vec<String> = children
+                    .filter_map(Result::ok)
+                    .map(|child| {
+                        let name = child.file_name().to_string_lossy().to_string();
+                        if child.file_type().is_ok_and(|ft| ft.is_dir()) {
+                            name + "/"
+                        } else {
+                            name
+                        }
+                    })
+                    .collect();
+                names.sort();
+                names
+            }
+            Err(e) => vec![format!("Cannot read directory: {e}")],
+        };
+    }
+
+    let mut bytes = Vec::with_capacity(max_bytes.min(PREVIEW_BYTES));
+    let read = fs::File::open(path)
+        .and_then(|file| file.take(max_bytes as u64 + 1).read_to_end(&mut bytes));
+    if let Err(e) = read {
+        return vec![format!("Cannot read file: {e}")];
+    }
+    let truncated = bytes.len() > max_bytes;
+    bytes.truncate(max_bytes);
+
+    // Same heuristic as git: a NUL byte means binary
+    if bytes.contains(&0) {
+        return bytes
+            .chunks(16)
+            .take(PREVIEW_HEX_BYTES / 16)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let hex: Vec<String> = chunk.iter().map(|b| format!("{b:02x}")).collect();
+                let ascii: String = chunk
+                    .iter()
+                    .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                    .collect();
+                format!("{:08x}  {:<47}  |{ascii}|", i * 16, hex.join(" "))
+            })
+            .collect();
+    }
+
+    let mut lines: Vec<String> = String::from_utf8_lossy(&bytes)
+        .lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:>4} │ {}", i + 1, line.replace('\t', "    ")))
+        .collect();
+    if truncated {
+        lines.push(format!("… (first {max_bytes} bytes)"));
+    }
+    lines
+}
+
+/// Text copied by `y`: the canonical path, the same one `Ctrl+S` prints
+fn clipboard_text(path: &Path) -> String {
+    canonicalize_path(path).display().to_string()
+}
+
+/// `file://` URL of `path`, for terminal hyperlinks
+fn file_url(path: &Path) -> Option<Url> {
+    Url::from_file_path(fs::canonicalize(path).ok()?).ok()
+}
+
+/// Wraps the cells `x..x + width` of row `y` in an OSC-8 hyperlink to `url`.
+/// Ratatui drops control characters from spans, so the escapes go straight into
+/// the buffer: each linked cell carries two columns of text and the diff skips
+/// the cell after it. A trailing single column is left unlinked.
+fn link_cells(buf: &mut Buffer, x: u16, y: u16, width: u16, url: &Url) {
+    let end = x + width;
+    let mut col = x;
+    while col < end {
+        let mut text = buf[(col, y)].symbol().to_string();
+        if text.width() < 2 {
+            if col + 1 >= end {
+                break;
+            }
+            text.push_str(buf[(col + 1, y)].symbol());
+        }
+        buf[(col, y)].set_symbol(&format!("\x1B]8;;{url}\x07{text}\x1B]8;;\x07"));
+        col += 2;
+    }
+}
+
+/// Split `name` into spans, underlining the characters at the matched char `positions`
+fn highlight_matches(name: &str, positions: &[usize], style: Style) -> Vec<Span<'static>> {
+    let matched_style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+
+    for (i, c) in name.chars().enumerate() {
+        let matched = positions.contains(&i);
+        if matched != run_matched && !run.is_empty() {
+            let s = if run_matched { matched_style } else { style };
+            spans.push(Span::styled(std::mem::take(&mut run), s));
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+
+    if !run.is_empty() {
+        spans.push(Span::styled(run, if run_matched { matched_style } else { style }));
+    }
+    spans
+}
+
 /// Convert lscolors style to ratatui style
 #[inline]
 fn convert_ls_style(ls_style: LsStyle) -> Style {
@@ -801,13 +1470,81 @@ fn convert_ls_style(ls_style: LsStyle) -> Style {
 fn handle_exit_action(action: ExitAction) -> anyhow::Result<()> {
     match action {
         ExitAction::PrintPath(path) => {
-            println!("{}", canonicalize_path(path.as_path()).display());
+            println!("{}", display_path(&canonicalize_path(path.as_path())));
         }
         ExitAction::None => {}
     }
     Ok(())
 }
 
+/// Compiled `[tui.expand]` rules: globs without a `/` match directory names,
+/// the others match paths relative to the root
+pub struct ExpandRules(Vec<(GlobMatcher, bool, usize)>);
+
+impl ExpandRules {
+    pub fn new(rules: &BTreeMap<String, usize>) -> anyhow::Result<Self> {
+        rules
+            .iter()
+            .map(|(pattern, &level)| {
+                let glob = GlobBuilder::new(pattern)
+                    .literal_separator(true)
+                    .build()
+                    .with_context(|| format!("Invalid [tui.expand] pattern '{pattern}'"))?;
+                Ok((glob.compile_matcher(), pattern.contains('/'), level))
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(Self)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Expansion level of the directory at `rel` (relative to the root), if a rule matches
+    fn level_for(&self, rel: &Path) -> Option<usize> {
+        let name = rel.file_name()?;
+        self.0
+            .iter()
+            .find(|(glob, by_path, _)| glob.is_match(if *by_path { rel } else { Path::new(name) }))
+            .map(|&(_, _, level)| level)
+    }
+}
+
+/// Columns taken by the list borders, the highlight symbol and a trailing space
+const INFO_MARGIN: usize = 5;
+
+/// Size (and, with `--info`, counts) shown to the right of an entry
+fn info_text(entry: &TreeEntry, args: &Args) -> String {
+    if args.info {
+        if entry.is_directory {
+            if args.no_size_for_dirs {
+                return String::new();
+            }
+            if let (Some(size), Some(files), Some(dirs)) = (entry.size, entry.files, entry.dirs) {
+                return format!("[{}, {} files, {} dirs]", args.format_size(size), files, dirs);
+            }
+        } else if let Some(size) = entry.size {
+            return format!("[{}]", args.format_size(size));
+        }
+    } else if args.size
+        && let Some(size) = entry.size
+    {
+        return format!("[{}]", args.format_size(size));
+    }
+    String::new()
+}
+
+/// Width of the widest info text, so the column stays put while scrolling
+fn info_column_width<'a>(entries: impl Iterator<Item = &'a TreeEntry>, args: &Args) -> usize {
+    entries.map(|entry| info_text(entry, args).chars().count()).max().unwrap_or(0)
+}
+
+/// Whether `entry` is the synthetic ".." entry pointing at the parent directory
+#[inline]
+fn is_back_entry(entry: &TuiEntry) -> bool {
+    entry.data.icon.as_deref() == Some("..")
+}
+
 fn open_file(path: &Path) -> anyhow::Result<()> {
     if !path.exists() {
         anyhow::bail!("File does not exist: {}", path.display());
@@ -830,3 +1567,595 @@ fn open_file(path: &Path) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Command showing `path` in the system file manager: directories are opened, files reveal
+/// their containing directory (selected where the platform supports it)
+fn reveal_command(path: &Path) -> Command {
+    #[cfg(target_os = "windows")]
+    let command = {
+        let mut command = Command::new("explorer");
+        if path.is_dir() {
+            command.arg(path);
+        } else {
+            command.arg(format!("/select,{}", path.display()));
+        }
+        command
+    };
+
+    #[cfg(target_os = "macos")]
+    let command = {
+        let mut command = Command::new("open");
+        if !path.is_dir() {
+            command.arg("-R");
+        }
+        command.arg(path);
+        command
+    };
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let command = {
+        let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+        let mut command = Command::new("xdg-open");
+        command.arg(if path.is_dir() { path } else { parent.unwrap_or(Path::new(".")) });
+        command
+    };
+
+    command
+}
+
+fn reveal_in_file_manager(path: &Path) -> anyhow::Result<()> {
+    if !path.exists() {
+        anyhow::bail!("File does not exist: {}", path.display());
+    }
+
+    reveal_command(path).spawn()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn entry(path: &str, depth: usize, is_directory: bool) -> TreeEntry {
+        TreeEntry { path: PathBuf::from(path), depth, is_directory, ..Default::default() }
+    }
+
+    fn sample_entries() -> Vec<TreeEntry> {
+        vec![
+            entry("/r/a", 1, true),
+            entry("/r/a/b", 2, true),
+            entry("/r/a/b/deep.txt", 3, false),
+            entry("/r/a/x.txt", 2, false),
+            entry("/r/z.txt", 1, false),
+        ]
+    }
+
+    fn visible_paths(app: &TuiApp) -> Vec<PathBuf> {
+        app.filtered_indices.iter().map(|&i| app.entry(i).data.path.clone()).collect()
+    }
+
+    /// The previous full-scan implementation: ".." then every direct child of `dir`
+    fn naive_visible_paths(entries: &[TreeEntry], dir: &Path) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = dir.parent().map(Path::to_path_buf).into_iter().collect();
+        paths.extend(
+            entries.iter().filter(|e| e.path.parent() == Some(dir)).map(|e| e.path.clone()),
+        );
+        paths
+    }
+
+    fn index_of(app: &TuiApp, path: &str) -> usize {
+        app.entries.iter().position(|e| e.data.path == Path::new(path)).unwrap()
+    }
+
+    #[test]
+    fn test_enter_directory_matches_full_scan() {
+        let entries = sample_entries();
+        let mut app = TuiApp::new(entries.clone(), "/r", None);
+
+        app.enter_directory(index_of(&app, "/r/a"));
+        assert_eq!(visible_paths(&app), naive_visible_paths(&entries, Path::new("/r/a")));
+
+        app.enter_directory(index_of(&app, "/r/a/b"));
+        assert_eq!(visible_paths(&app), naive_visible_paths(&entries, Path::new("/r/a/b")));
+
+        app.go_up();
+        assert_eq!(app.current_dir, Path::new("/r/a"));
+        assert_eq!(visible_paths(&app), naive_visible_paths(&entries, Path::new("/r/a")));
+
+        app.go_up();
+        assert_eq!(app.current_dir, Path::new("/r"));
+        assert!(app.back_entry_index().is_none());
+        assert_eq!(app.entries.len(), entries.len());
+    }
+
+    #[test]
+    fn test_refresh_keeps_expansion_and_selection() {
+        let mut entries = sample_entries();
+        entries.push(entry("/r/c", 1, true));
+        entries.push(entry("/r/c/y.txt", 2, false));
+        let mut app = TuiApp::new(entries.clone(), "/r", None);
+
+        for dir in ["/r/a", "/r/a/b"] {
+            let idx = index_of(&app, dir);
+            app.entries[idx].expanded = true;
+        }
+        app.rebuild_visible_list();
+        let deep = index_of(&app, "/r/a/b/deep.txt");
+        app.list_state.select(app.filtered_indices.iter().position(|&i| i == deep));
+        let before = visible_paths(&app);
+
+        app.refresh_entries(entries);
+
+        let expanded: Vec<_> =
+            app.entries.iter().filter(|e| e.expanded).map(|e| e.data.path.clone()).collect();
+        assert_eq!(expanded, [PathBuf::from("/r/a"), PathBuf::from("/r/a/b")]);
+        assert_eq!(visible_paths(&app), before);
+        assert_eq!(app.get_current_entry().unwrap().data.path, Path::new("/r/a/b/deep.txt"));
+    }
+
+    #[test]
+    fn test_select_first_and_last() {
+        let mut app = TuiApp::new(sample_entries(), "/r", None);
+        let last = app.filtered_indices.len() - 1;
+        assert!(last > 0);
+
+        app.select_last();
+        assert_eq!(app.list_state.selected(), Some(last));
+        app.select_first();
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        app.filtered_indices.clear();
+        app.select_last();
+        assert_eq!(app.list_state.selected(), None);
+        app.select_first();
+        assert_eq!(app.list_state.selected(), None);
+    }
+
+    #[test]
+    fn test_expand_and_collapse_all() {
+        let mut app = TuiApp::new(sample_entries(), "/r", None);
+        let collapsed = app.filtered_indices.len();
+
+        app.set_all_expanded(true);
+        assert_eq!(app.filtered_indices.len(), sample_entries().len());
+
+        // Keep the selection on the same entry, then on its visible ancestor
+        let deep = index_of(&app, "/r/a/b/deep.txt");
+        app.list_state.select(app.filtered_indices.iter().position(|&i| i == deep));
+        app.set_all_expanded(true);
+        assert_eq!(app.get_current_entry().unwrap().data.path, Path::new("/r/a/b/deep.txt"));
+
+        app.set_all_expanded(false);
+        assert_eq!(app.filtered_indices.len(), collapsed);
+        assert_eq!(app.get_current_entry().unwrap().data.path, Path::new("/r/a"));
+    }
+
+    #[test]
+    fn test_move_selection_by_clamps() {
+        let mut app = TuiApp::new(sample_entries(), "/r", None);
+        let last = app.filtered_indices.len() - 1;
+        app.list_state.select(Some(0));
+
+        app.move_selection_by(1);
+        assert_eq!(app.list_state.selected(), Some(1));
+        app.move_selection_by(100);
+        assert_eq!(app.list_state.selected(), Some(last));
+        app.move_selection_by(-100);
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        app.viewport_rows = 1;
+        assert_eq!((app.page_rows(true), app.page_rows(false)), (1, 1));
+        app.viewport_rows = 20;
+        assert_eq!((app.page_rows(true), app.page_rows(false)), (20, 10));
+
+        app.filtered_indices.clear();
+        app.list_state.select(None);
+        app.move_selection_by(5);
+        assert_eq!(app.list_state.selected(), None);
+    }
+
+    #[test]
+    fn test_file_cap_and_expand_more() {
+        let mut entries = vec![entry("/r/a", 1, true)];
+        entries.extend((1..=5).map(|i| entry(&format!("/r/a/f{i}.txt"), 2, false)));
+        entries.push(entry("/r/z.txt", 1, false));
+
+        let mut app = TuiApp::new(entries.clone(), "/r", None);
+        app.set_file_cap(Some(2));
+        app.enter_directory(index_of(&app, "/r/a"));
+
+        let visible = visible_paths(&app);
+        assert_eq!(
+            visible,
+            ["/r", "/r/a/f1.txt", "/r/a/f2.txt", "/r/a"].map(PathBuf::from).to_vec()
+        );
+        let more_idx = *app.filtered_indices.last().unwrap();
+        assert_eq!(app.entry(more_idx).more, Some(3));
+
+        // Selecting "… 3 more" reveals the remaining files and keeps the cursor in place
+        app.list_state.select(Some(3));
+        assert!(app.expand_more(more_idx));
+        assert_eq!(visible_paths(&app), naive_visible_paths(&entries, Path::new("/r/a")));
+        assert_eq!(app.list_state.selected(), Some(3));
+        assert!(app.filtered_indices.iter().all(|&i| app.entry(i).more.is_none()));
+
+        // Regular entries are not expanded as "more"
+        assert!(!app.expand_more(index_of(&app, "/r/a/f1.txt")));
+    }
+
+    #[test]
+    fn test_info_column_width() {
+        let args = Args::parse_from(["wisu", "-s"]);
+        let mut small = entry("/r/a.txt", 1, false);
+        small.size = Some(10);
+        let mut large = entry("/r/b.txt", 1, false);
+        large.size = Some(1536);
+        let no_size = entry("/r/c", 1, true);
+
+        // "[10 B]" vs "[1.5 KiB]"
+        assert_eq!(info_column_width([&small, &large, &no_size].into_iter(), &args), 9);
+        assert_eq!(info_column_width([&no_size].into_iter(), &args), 0);
+
+        let args = Args::parse_from(["wisu"]);
+        assert_eq!(info_column_width([&small, &large].into_iter(), &args), 0);
+    }
+
+    #[test]
+    fn test_delete_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("a/b/deep.txt"), "x").unwrap();
+        std::fs::write(root.join("z.txt"), "x").unwrap();
+        let at = |rel: &str, depth, is_dir| TreeEntry {
+            path: root.join(rel),
+            depth,
+            is_directory: is_dir,
+            ..Default::default()
+        };
+        let entries = vec![
+            at("a", 1, true),
+            at("a/b", 2, true),
+            at("a/b/deep.txt", 3, false),
+            at("z.txt", 1, false),
+        ];
+        let mut app = TuiApp::new(entries, root, None);
+
+        // Selecting "a" asks for confirmation before anything is removed
+        app.list_state.select(Some(0));
+        app.request_delete();
+        let Mode::Confirm(PendingAction::Delete(path)) = app.mode.clone() else {
+            panic!("no prompt")
+        };
+        assert!(root.join("a").exists());
+
+        app.delete_entry(&path).unwrap();
+        assert!(!root.join("a").exists());
+        let paths: Vec<_> = app.entries.iter().map(|e| e.data.path.clone()).collect();
+        assert_eq!(paths, [root.join("z.txt")]);
+        assert_eq!(visible_paths(&app), [root.join("z.txt")]);
+        assert_eq!(app.list_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_delete_never_targets_back_entry() {
+        let mut app = TuiApp::new(sample_entries(), "/r", None);
+        app.enter_directory(index_of(&app, "/r/a"));
+        // ".." comes first in a subdirectory
+        app.list_state.select(Some(0));
+        app.request_delete();
+        assert!(app.mode == Mode::Normal);
+
+        // Neither the parent nor the root is deleted when asked directly
+        assert!(app.delete_entry(Path::new("/r")).is_err());
+    }
+
+    #[test]
+    fn test_delete_after_refresh_targets_confirmed_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/keep.txt"), "x").unwrap();
+        std::fs::write(root.join("sub/target.txt"), "x").unwrap();
+        let at = |rel: &str, depth, is_dir| TreeEntry {
+            path: root.join(rel),
+            depth,
+            is_directory: is_dir,
+            ..Default::default()
+        };
+        let scan = |extra: &[&str]| {
+            let mut entries = vec![at("sub", 1, true)];
+            entries.extend(extra.iter().map(|rel| at(rel, 2, false)));
+            entries.push(at("sub/target.txt", 2, false));
+            entries
+        };
+        let mut app = TuiApp::new(scan(&[]), root, None);
+        app.enter_directory(index_of(&app, &root.join("sub").to_string_lossy()));
+        app.list_state.select(visible_paths(&app).iter().position(|p| p.ends_with("target.txt")));
+        app.request_delete();
+        let Mode::Confirm(PendingAction::Delete(path)) = app.mode.clone() else {
+            panic!("no prompt")
+        };
+
+        // A watch refresh lands while the prompt is open and shifts every index
+        app.refresh_entries(scan(&["sub/keep.txt"]));
+
+        assert_eq!(app.delete_entry(&path).unwrap(), root.join("sub/target.txt"));
+        assert!(!root.join("sub/target.txt").exists());
+        assert!(root.join("sub/keep.txt").exists());
+        assert!(root.join("sub").exists());
+    }
+
+    #[test]
+    fn test_clipboard_text() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "x").unwrap();
+
+        let canonical = std::fs::canonicalize(dir.path()).unwrap();
+        let entry = entry(&dir.path().join("sub/../notes.txt").to_string_lossy(), 1, false);
+        assert_eq!(clipboard_text(&entry.path), canonical.join("notes.txt").display().to_string());
+    }
+
+    #[test]
+    fn test_handle_event() {
+        use ratatui::crossterm::event::{KeyEventState, MouseEvent};
+
+        let mut app = TuiApp::new(sample_entries(), "/r", None);
+        app.list_state.select(Some(0));
+        let scroll = |kind| {
+            Event::Mouse(MouseEvent { kind, column: 0, row: 0, modifiers: KeyModifiers::NONE })
+        };
+
+        // Each event is consumed once: one scroll moves the selection by one row
+        assert_eq!(
+            handle_event(&mut app, scroll(MouseEventKind::ScrollDown)),
+            EventAction::Handled
+        );
+        assert_eq!(app.list_state.selected(), Some(1));
+        assert_eq!(handle_event(&mut app, scroll(MouseEventKind::ScrollUp)), EventAction::Handled);
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        let press = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(handle_event(&mut app, Event::Key(press)), EventAction::Key(press));
+        let release = KeyEvent { kind: KeyEventKind::Release, state: KeyEventState::NONE, ..press };
+        assert_eq!(handle_event(&mut app, Event::Key(release)), EventAction::Ignored);
+        assert_eq!(handle_event(&mut app, Event::Resize(80, 24)), EventAction::Resized);
+        assert_eq!(handle_event(&mut app, Event::FocusGained), EventAction::Ignored);
+        assert_eq!(app.list_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_row_to_index() {
+        let mut entries = sample_entries();
+        entries.extend((0..10).map(|i| entry(&format!("/r/f{i}.txt"), 1, false)));
+        let mut app = TuiApp::new(entries, "/r", None);
+        let rows = app.filtered_indices.len();
+        // Bordered list from screen row 1 (below the breadcrumb): entries on rows 2..=6
+        app.list_area = Rect::new(0, 1, 40, 7);
+
+        assert_eq!(app.row_to_index(1), None); // top border
+        assert_eq!(app.row_to_index(2), Some(0));
+        assert_eq!(app.row_to_index(6), Some(4));
+        assert_eq!(app.row_to_index(7), None); // bottom border
+
+        // Scrolled down: the first visible row is `offset`
+        *app.list_state.offset_mut() = rows - 3;
+        assert_eq!(app.row_to_index(2), Some(rows - 3));
+        assert_eq!(app.row_to_index(4), Some(rows - 1));
+        assert_eq!(app.row_to_index(5), None); // past the last entry
+
+        // A first click selects, a second one on a directory expands it
+        *app.list_state.offset_mut() = 0;
+        app.list_state.select(Some(1));
+        app.click(5, 2);
+        assert_eq!(app.list_state.selected(), Some(0));
+        assert!(!app.entries[index_of(&app, "/r/a")].expanded);
+        app.click(5, 2);
+        assert!(app.entries[index_of(&app, "/r/a")].expanded);
+
+        // While searching or confirming, clicks only select
+        for mode in [Mode::Search, Mode::Confirm(PendingAction::Delete(PathBuf::from("/r/b")))] {
+            app.mode = mode;
+            app.click(5, 2);
+            assert!(app.entries[index_of(&app, "/r/a")].expanded);
+            app.click(5, 3);
+            assert_eq!(app.list_state.selected(), Some(1));
+            app.list_state.select(Some(0));
+        }
+    }
+
+    #[test]
+    fn test_preview_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("notes.txt");
+        std::fs::write(&text, "first\n\tsecond\n").unwrap();
+        assert_eq!(preview_lines(&text, 1024), ["   1 │ first", "   2 │     second"]);
+
+        // Capped read: only whole lines of the first bytes, then a marker
+        assert_eq!(preview_lines(&text, 3), ["   1 │ fir", "… (first 3 bytes)"]);
+
+        let binary = dir.path().join("blob.bin");
+        std::fs::write(&binary, [0x7f, b'E', b'L', b'F', 0, 1]).unwrap();
+        assert_eq!(
+            preview_lines(&binary, 1024),
+            [format!("00000000  {:<47}  |.ELF..|", "7f 45 4c 46 00 01")]
+        );
+
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        assert_eq!(preview_lines(dir.path(), 1024), ["blob.bin", "notes.txt", "sub/"]);
+    }
+
+    #[test]
+    fn test_link_cells() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 1));
+        buf.set_string(0, 0, "main.rs", Style::default());
+        let url = Url::parse("file:///r/main.rs").unwrap();
+        link_cells(&mut buf, 0, 0, 7, &url);
+
+        let link = |text: &str| format!("\x1B]8;;file:///r/main.rs\x07{text}\x1B]8;;\x07");
+        assert_eq!(buf[(0, 0)].symbol(), link("ma"));
+        assert_eq!(buf[(2, 0)].symbol(), link("in"));
+        assert_eq!(buf[(4, 0)].symbol(), link(".r"));
+        // The odd last column stays plain text
+        assert_eq!(buf[(6, 0)].symbol(), "s");
+    }
+
+    #[test]
+    fn test_reveal_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "x").unwrap();
+
+        let args = |path: &Path| -> (String, Vec<String>) {
+            let command = reveal_command(path);
+            let program = command.get_program().to_string_lossy().to_string();
+            (program, command.get_args().map(|a| a.to_string_lossy().to_string()).collect())
+        };
+        let (dir_program, dir_args) = args(dir.path());
+        let (file_program, file_args) = args(&file);
+
+        if cfg!(target_os = "windows") {
+            assert_eq!(dir_program, "explorer");
+            assert_eq!(dir_args, [dir.path().display().to_string()]);
+            assert_eq!(file_args, [format!("/select,{}", file.display())]);
+        } else if cfg!(target_os = "macos") {
+            assert_eq!(dir_program, "open");
+            assert_eq!(dir_args, [dir.path().display().to_string()]);
+            assert_eq!(file_args, ["-R".to_string(), file.display().to_string()]);
+        } else {
+            assert_eq!(dir_program, "xdg-open");
+            // A file opens its containing directory, just like the directory itself
+            assert_eq!(dir_args, [dir.path().display().to_string()]);
+            assert_eq!(file_args, dir_args);
+        }
+        assert_eq!(file_program, dir_program);
+    }
+
+    #[test]
+    fn test_search_matchers() {
+        let entries = vec![
+            entry("/r/main.rs", 1, false),
+            entry("/r/domain.rs", 1, false),
+            entry("/r/my_data.txt", 1, false),
+        ];
+        let mut app = TuiApp::new(entries, "/r", None);
+        let mut search = |matcher| {
+            app.search_matcher = matcher;
+            app.search_query = "MA".to_string();
+            app.apply_search_filter();
+            app.filtered_indices
+                .iter()
+                .map(|&i| {
+                    app.entries[i].data.path.file_name().unwrap().to_string_lossy().to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(search(SearchMatcher::Substring), ["main.rs", "domain.rs"]);
+        assert_eq!(search(SearchMatcher::Prefix), ["main.rs"]);
+        // Fuzzy matches rank word-start hits above mid-word ones
+        assert_eq!(search(SearchMatcher::Fuzzy), ["main.rs", "my_data.txt", "domain.rs"]);
+
+        let args: Args = toml::from_str("[tui]\nsearch = \"prefix\"\n").unwrap();
+        assert_eq!(args.tui.search, Some(SearchMatcher::Prefix));
+    }
+
+    #[test]
+    fn test_fuzzy_search_ranks_by_score() {
+        let entries = vec![
+            entry("/r/mirrors.toml", 1, false),
+            entry("/r/README.md", 1, false),
+            entry("/r/main.rs", 1, false),
+        ];
+        let mut app = TuiApp::new(entries, "/r", None);
+        app.search_matcher = SearchMatcher::Fuzzy;
+        app.search_query = "mrs".to_string();
+        app.apply_search_filter();
+
+        let names: Vec<_> = app
+            .filtered_indices
+            .iter()
+            .map(|&i| app.entries[i].data.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["main.rs", "mirrors.toml"]);
+
+        app.mode = Mode::Search;
+        assert_eq!(app.search_highlight("main.rs"), Some(vec![0, 5, 6]));
+        let spans = highlight_matches("main.rs", &[0, 5, 6], Style::default());
+        let text: Vec<_> = spans.iter().map(|s| s.content.to_string()).collect();
+        assert_eq!(text, ["m", "ain.", "rs"]);
+    }
+
+    #[test]
+    fn test_keymap_from_config() {
+        let args: Args = toml::from_str("[keys]\nquit = \"x\"\nsearch = \"q\"\n").unwrap();
+
+        assert_eq!(args.keys.action('x'), Some(KeyAction::Quit));
+        // `search` takes `q` from `quit`, and no longer answers to its default `/`
+        assert_eq!(args.keys.action('q'), Some(KeyAction::Search));
+        assert_eq!(args.keys.action('/'), None);
+        assert_eq!(args.keys.key(KeyAction::Search), 'q');
+        // Untouched actions keep their defaults
+        assert_eq!(args.keys.action('r'), Some(KeyAction::Refresh));
+        assert_eq!(Args::default().keys.action('q'), Some(KeyAction::Quit));
+
+        assert!(toml::from_str::<Args>("[keys]\nfly = \"f\"\n").is_err());
+    }
+
+    #[test]
+    fn test_keymap_conflicts() {
+        let keys = |toml: &str| toml::from_str::<Args>(toml).unwrap().keys;
+
+        let conflict = keys("[keys]\ndelete = \"x\"\nquit = \"x\"\n");
+        let err = conflict.validate().unwrap_err().to_string();
+        assert_eq!(err, "[keys] quit and delete are both bound to \"x\"");
+
+        let reserved = keys("[keys]\nbottom = \"g\"\n").validate().unwrap_err();
+        assert!(reserved.to_string().contains("reserved for `gg`"));
+
+        // Taking another action's default key is not a conflict
+        let taken = keys("[keys]\nsearch = \"q\"\n");
+        taken.validate().unwrap();
+        assert_eq!(taken.action('q'), Some(KeyAction::Search));
+        assert_eq!(taken.action('/'), None);
+    }
+
+    #[test]
+    fn test_expand_rules_from_config() {
+        let args: Args = toml::from_str(
+            r#"
+            expand_level = 2
+
+            [tui.expand]
+            src = 99
+            target = 0
+            "docs/*" = 1
+            "#,
+        )
+        .unwrap();
+        let rules = ExpandRules::new(&args.tui.expand).unwrap();
+
+        let entries = vec![
+            entry("/r/docs", 1, true),
+            entry("/r/docs/api", 2, true),
+            entry("/r/docs/api/v1", 3, true),
+            entry("/r/src", 1, true),
+            entry("/r/src/a", 2, true),
+            entry("/r/src/a/b", 3, true),
+            entry("/r/target", 1, true),
+            entry("/r/target/debug", 2, true),
+        ];
+        let mut app = TuiApp::new(entries, "/r", None);
+        app.apply_initial_expansion(args.expand_level, &rules);
+
+        let expanded: HashSet<PathBuf> =
+            app.entries.iter().filter(|e| e.expanded).map(|e| e.data.path.clone()).collect();
+        let expected: HashSet<PathBuf> =
+            ["/r/docs", "/r/docs/api", "/r/src", "/r/src/a", "/r/src/a/b"]
+                .map(PathBuf::from)
+                .into();
+        assert_eq!(expanded, expected);
+    }
+}
@@ -1,35 +1,149 @@
-use crate::app::Args;
-use crate::common::{icons, tree};
+use crate::app::{Args, Column, Format, LONG_COLUMNS, TABLE_COLUMNS};
+use crate::common::{git, icons, tree};
 use crate::utils::{dir, format};
+use crate::workers::export;
 use colored::Colorize;
-use lscolors::LsColors;
+use lscolors::{Colorable, LsColors};
+use ratatui::crossterm::{cursor, execute, terminal};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
 use std::fs;
 use std::io::{self, Write};
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
 use url::Url;
 
-/// Runs the classic directory tree view
-pub fn run(args: &Args, ls_colors: &LsColors) -> anyhow::Result<()> {
+/// Extension groups and their default colors, shared by entry styling and the legend
+const EXTENSION_COLORS: &[(&str, &[&str], colored::Color)] = &[
+    ("source", &["rs", "c", "cpp", "py", "php", "html", "css", "js"], colored::Color::Cyan),
+    ("archive", &["zip", "tar", "gz", "rar", "7zip"], colored::Color::Yellow),
+    ("image", &["psd", "svg", "jpg", "jpeg", "png", "gif", "bmp", "tiff"], colored::Color::Magenta),
+    ("video", &["mp4", "mkv", "avi", "mov", "flv", "wmv"], colored::Color::Magenta),
+    (
+        "document",
+        &["pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "pps", "ppsx"],
+        colored::Color::BrightBlack,
+    ),
+];
+
+/// Runs the classic directory tree view, returning how many files (directories with `-d`)
+/// it listed; None in watch mode, which has no final listing
+pub fn run(args: &Args, ls_colors: &LsColors) -> anyhow::Result<Option<usize>> {
+    if args.owner && args.columns.as_ref().is_some_and(|columns| !columns.contains(&Column::Owner))
+    {
+        eprintln!("Warning: --owner has no effect with --columns; add the owner column instead");
+    }
+
+    if args.watch {
+        let watcher = tree::Tree::create_watcher(args)?;
+        watch(&watcher, || {
+            execute!(
+                io::stdout(),
+                terminal::Clear(terminal::ClearType::All),
+                cursor::MoveTo(0, 0)
+            )?;
+            render(args, ls_colors, false).map(|_| ())
+        })?;
+        return Ok(None);
+    }
+
+    render(args, ls_colors, true).map(Some)
+}
+
+/// Renders once, then again after every debounced filesystem change
+fn watch(
+    watcher: &tree::TreeWatcher,
+    mut render: impl FnMut() -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    render()?;
+    while watcher.wait_for_debounced_change(tree::WATCH_DEBOUNCE) {
+        render()?;
+    }
+    Ok(())
+}
+
+/// Scans and prints the tree followed by the stats line, returning the listed count
+fn render(args: &Args, ls_colors: &LsColors, show_progress: bool) -> anyhow::Result<usize> {
     let start_time = Instant::now();
 
     // ─────────────── Data preparation ───────────────
-    let tree = tree::Tree::prepare(args, true)?;
+    let tree = tree::Tree::prepare(args, show_progress)?;
+
+    // Entries per depth, for `--stats-json` (the tree is consumed by printing)
+    let depths =
+        if args.stats_json.is_some() { export::depth_counts(&tree) } else { BTreeMap::new() };
+    let entries = tree.entries.len();
 
     // ─────────────── Print ───────────────
-    let (dir_count, file_count, size) = print_tree(tree, ls_colors, args)?;
+    let size_limit_reached = tree.size_limit_reached;
+    let (dir_count, file_count, size) = match args.format {
+        _ if args.root_only_stats => print_root_totals(&tree, args)?,
+        Format::Table | Format::Long => print_table(&tree, args)?,
+        // Export formats never reach the view (see `main`)
+        Format::Tree
+        | Format::Json
+        | Format::Xml
+        | Format::Csv
+        | Format::Tsv
+        | Format::Yaml
+        | Format::Ndjson => print_tree(tree, ls_colors, args)?,
+    };
 
     let elapsed = start_time.elapsed();
 
+    // On stderr, so piped `--flat`/table output stays clean
+    if size_limit_reached && let Some(limit) = args.max_total_size {
+        eprintln!(
+            "Scan stopped at --max-total-size {}: the tree is partial",
+            args.format_size(limit.0)
+        );
+    }
+
+    if args.legend {
+        print_legend(args)?;
+    }
+
     if args.stats {
+        let total = if args.bytes {
+            format!("{} ({size} bytes)", args.format_size_human(size))
+        } else {
+            args.format_size(size)
+        };
+        let duration = format::duration(elapsed);
+        // `µs` is the one non-ASCII unit
+        let duration = if args.ascii { duration.replace('µ', "u") } else { duration };
         writeln!(
             io::stdout(),
-            "\n{}, {dir_count} directories, {file_count} files ( {:.2?} )",
-            format::size(size),
-            elapsed
+            "\n{total}, {dir_count} directories, {file_count} files ( {duration} )"
         )?;
     }
 
-    Ok(())
+    if let Some(path) = &args.stats_json {
+        let summary = export::StatsSummary {
+            dirs: dir_count,
+            files: file_count,
+            size,
+            scan: export::ScanStats::new(entries, elapsed),
+            depths,
+        };
+        summary.write(path)?;
+    }
+
+    Ok(if args.dirs_only { dir_count } else { file_count })
+}
+
+/// Prints only the root line with the recursive totals of the whole tree
+fn print_root_totals(tree: &tree::Tree, args: &Args) -> io::Result<(usize, usize, u64)> {
+    let (dir_count, file_count, size) = tree.totals();
+
+    writeln!(
+        io::stdout(),
+        "{}{}",
+        dir::display_path(&args.path).blue().bold(),
+        format!(" ( {}  {dir_count} dirs, {file_count} files )", args.format_size(size)).dimmed()
+    )?;
+
+    Ok((dir_count, file_count, size))
 }
 
 pub fn print_tree(
@@ -40,24 +154,45 @@ pub fn print_tree(
     // ───────────── ROOT ─────────────
     let metadata = fs::metadata(&args.path).ok();
     let root_is_dir = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(true);
+    let root_modified = metadata.as_ref().and_then(|m| m.modified().ok());
 
-    let root_icon = if args.icons {
-        format!("{} ", icons::get_icon_for_path(&args.path, root_is_dir))
+    let root_icon = if args.shows_icon(root_is_dir) {
+        format!("{} ", icons::get_icon_for_path(&args.path, root_is_dir, args.icon_set))
     } else {
         String::new()
     };
 
-    let root_permissions =
-        if args.permissions { dir::get_permission(metadata) } else { String::new() };
+    let root_owner = match &metadata {
+        Some(metadata) if args.owner || args.has_column(Column::Owner) => {
+            dir::OwnerNames::default().owner(metadata)
+        }
+        _ => String::new(),
+    };
+
+    let root_permissions = if args.permissions || args.has_column(Column::Perms) {
+        dir::get_permission(metadata, args.perms_style())
+    } else {
+        String::new()
+    };
 
     let root_entries = tree.entries_at_depth(1);
 
+    // `--git-status` overrides the type colors of untracked and ignored entries
+    let git_status = if args.git_status { git::StatusMap::discover(&args.path) } else { None };
+    let style_name = |info: &tree::TreeEntry, file_type| {
+        let status = git_status.as_ref().and_then(|statuses| statuses.get(&info.path));
+        match status {
+            Some(status) => style_git_status(info, status),
+            None => style_entry_name(info, file_type, ls_colors),
+        }
+    };
+
     let root_size: u64 = root_entries.iter().map(|(_, info)| info.size.unwrap_or(0)).sum();
 
-    let root_size_str = if args.info || args.size {
+    let root_size_str = if (args.info || args.size) && !args.no_size_for_dirs {
         format!(
             " ( {}  {} dirs, {} files )",
-            format::size(root_size),
+            args.format_size(root_size),
             root_entries
                 .iter()
                 .filter(|(entry, _)| entry.file_type().is_some_and(|ft| ft.is_dir()))
@@ -71,72 +206,200 @@ pub fn print_tree(
         String::new()
     };
 
-    writeln!(
-        io::stdout(),
-        "{}{}{}{}",
-        root_permissions.dimmed(),
-        root_icon,
-        args.path.display().to_string().blue().bold(),
-        root_size_str.dimmed()
-    )?;
+    if args.no_recursion || args.flat {
+        // Flat listing: no root header
+    } else if let Some(columns) = &args.columns {
+        let name = format!(
+            "{}{}{}",
+            root_icon,
+            dir::display_path(&args.path).blue().bold(),
+            root_size_str.dimmed()
+        );
+        writeln!(
+            io::stdout(),
+            "{}",
+            render_columns(
+                columns,
+                &style_permissions(&root_permissions, args.color_perms),
+                &root_owner,
+                &args.format_size(root_size),
+                root_modified,
+                &name
+            )
+        )?;
+    } else {
+        writeln!(
+            io::stdout(),
+            "{}{}{}{}{}",
+            style_permissions(&root_permissions, args.color_perms),
+            root_owner.dimmed(),
+            root_icon,
+            dir::display_path(&args.path).blue().bold(),
+            root_size_str.dimmed()
+        )?;
+    }
 
     // ───────────── ENTRIES ─────────────
     let mut dir_count = 0usize;
     let mut file_count = 0usize;
     let mut path_stack: Vec<bool> = Vec::new();
 
-    for (i, entry) in tree.entries.iter().enumerate() {
-        let c_info = &tree.tree_info[i];
-        let depth = c_info.depth;
+    // Aggregate size of each directory, for --percent
+    let dir_sizes: HashMap<&Path, u64> = if args.percent {
+        tree.tree_info
+            .iter()
+            .filter(|info| info.is_directory)
+            .map(|info| (info.path.as_path(), info.size.unwrap_or(0)))
+            .chain([(args.path.as_path(), root_size)])
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    // Scanned entries, each archive followed by its virtual contents (`--into-archives`)
+    let rows: Vec<_> = tree
+        .entries
+        .iter()
+        .zip(&tree.tree_info)
+        .flat_map(|(entry, info)| {
+            let contents = tree.archive_entries.get(&info.path).into_iter().flatten();
+            std::iter::once((info, entry.file_type())).chain(contents.map(|c| (c, None)))
+        })
+        .collect();
+
+    // `--collapse-single-child`: folded directories join their child's line (`a/b/c`)
+    // and everything below them moves up one level per folded directory
+    let folded = if args.collapse_single_child && !args.flat {
+        single_child_chains(&rows)
+    } else {
+        HashSet::new()
+    };
+    let mut child_shift: HashMap<&Path, usize> = HashMap::new();
+    let mut chain: Option<(String, &str)> = None;
+
+    for (i, &(c_info, file_type)) in rows.iter().enumerate() {
+        let shift = c_info.path.parent().and_then(|p| child_shift.get(p)).copied().unwrap_or(0);
+        let depth = c_info.depth - shift;
+
+        if folded.contains(&i) {
+            child_shift.insert(&c_info.path, shift + 1);
+            dir_count += 1;
+            let (names, _) = chain.get_or_insert_with(|| (String::new(), &c_info.connector));
+            names.push_str(&style_name(c_info, file_type));
+            names.push('/');
+            continue;
+        }
+        if shift > 0 {
+            child_shift.insert(&c_info.path, shift);
+        }
+        let (chain_names, connector) = chain.take().unwrap_or(("".into(), &c_info.connector));
 
         // Aggiorna stack in base alla profondità
         while path_stack.len() >= depth {
             path_stack.pop();
         }
-        path_stack.push(c_info.connector == "└──");
+        path_stack.push(connector == "└──");
 
-        let mut prefix = String::new();
-        for &is_last in &path_stack[..path_stack.len() - 1] {
-            prefix.push_str(if is_last { "    " } else { "│   " });
-        }
+        // Indentation plus connector glyphs, or plain indentation with --no-connector
+        let branch = if args.no_recursion || args.flat {
+            String::new()
+        } else if args.no_connector {
+            "    ".repeat(depth)
+        } else {
+            compute_prefix(&path_stack, connector, args.ascii)
+        };
+        let branch = if args.depth_markers { format!("[{depth}] {branch}") } else { branch };
 
-        // Conteggi
-        if c_info.is_directory {
+        // Conteggi (archive contents are not on disk)
+        if c_info.in_archive {
+        } else if c_info.is_directory {
             dir_count += 1;
         } else {
             file_count += 1;
         }
+        if let Some((dirs, files)) = c_info.collapsed {
+            dir_count += dirs;
+            file_count += files;
+        }
 
-        let size_str = if args.info {
-            if c_info.is_directory {
+        let size_str = if c_info.collapsed.is_some() {
+            format!("  [ {}, collapsed ]", args.format_size(c_info.size.unwrap_or(0)))
+        } else if args.info {
+            let size = c_info.size.unwrap_or(0);
+            let percent = c_info
+                .path
+                .parent()
+                .and_then(|parent| dir_sizes.get(parent))
+                .and_then(|&parent_size| percent_of(size, parent_size))
+                .map(|p| format!(" ({p:.0}%)"))
+                .unwrap_or_default();
+            if c_info.is_directory && args.no_size_for_dirs {
+                String::new()
+            } else if c_info.is_directory {
                 format!(
-                    "  [ {}  {} dirs, {} files ]",
-                    format::size(c_info.size.unwrap_or(0)),
+                    "  [ {}{}  {} dirs, {} files ]",
+                    args.format_size(size),
+                    percent,
                     c_info.dirs.unwrap_or(0),
                     c_info.files.unwrap_or(0)
                 )
             } else {
-                format!("  [ {} ]", format::size(c_info.size.unwrap_or(0)))
+                format!("  [ {}{} ]", args.format_size(size), percent)
             }
         } else if args.size && !c_info.is_directory {
-            c_info.size.map(|s| format!(" ({})", format::size(s))).unwrap_or_default()
+            c_info.size.map(|s| format!(" ({})", args.format_size(s))).unwrap_or_default()
         } else {
             String::new()
         };
 
-        let styled_name = style_entry_name(entry.path(), ls_colors);
-        let final_name = if args.hyperlinks && !c_info.is_directory {
-            make_hyperlink(entry.path(), styled_name)
+        // `--flat`: the name is prefixed with its parent directories instead
+        let chain_names =
+            if args.flat { flat_parent(&c_info.path, &args.path) } else { chain_names };
+        let styled_name = chain_names + &style_name(c_info, file_type);
+        let final_name = if c_info.in_archive {
+            styled_name.italic().to_string()
+        } else if args.hyperlinks && !c_info.is_directory {
+            make_hyperlink(&c_info.path, styled_name)
         } else {
-            styled_name.to_string()
+            styled_name
+        };
+        let final_name = match c_info.tags_label() {
+            Some(tags) => format!("{final_name} {}", tags.yellow()),
+            None => final_name,
         };
 
+        if let Some(columns) = &args.columns {
+            let name = format!(
+                "{}{}{}{}",
+                branch,
+                c_info.icon.clone().unwrap_or_default(),
+                final_name,
+                size_str.dimmed()
+            );
+            writeln!(
+                io::stdout(),
+                "{}",
+                render_columns(
+                    columns,
+                    &style_permissions(
+                        c_info.permissions.as_deref().unwrap_or_default(),
+                        args.color_perms
+                    ),
+                    c_info.owner.as_deref().unwrap_or_default(),
+                    &args.format_size(c_info.size.unwrap_or(0)),
+                    c_info.modified,
+                    &name
+                )
+            )?;
+            continue;
+        }
+
         writeln!(
             io::stdout(),
-            "{}{}{} {}{}{}",
-            c_info.permissions.clone().unwrap_or_default().dimmed(),
-            prefix,
-            c_info.connector,
+            "{}{}{}{}{}{}",
+            style_permissions(c_info.permissions.as_deref().unwrap_or_default(), args.color_perms),
+            c_info.owner.as_deref().unwrap_or_default().dimmed(),
+            branch,
             c_info.icon.clone().unwrap_or_default(),
             final_name,
             size_str.dimmed()
@@ -146,43 +409,227 @@ pub fn print_tree(
     Ok((dir_count, file_count, root_size))
 }
 
-#[inline]
-fn style_entry_name(path: &std::path::Path, ls_colors: &LsColors) -> colored::ColoredString {
-    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+/// Parent directories of `path` relative to `root`, with a trailing `/` (`a/b/`)
+fn flat_parent(path: &Path, root: &Path) -> String {
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    match rel.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => format!("{}/", parent.display()),
+        _ => String::new(),
+    }
+}
 
-    // safe metadata
-    let metadata = fs::metadata(path).ok();
-
-    // Default color based on type/extension
-    let mut styled = if let Some(metadata) = &metadata {
-        if metadata.is_dir() {
-            name.blue().bold()
-        } else if is_executable(path, metadata) {
-            name.green()
-        } else if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            match ext.to_lowercase().as_str() {
-                "rs" | "c" | "cpp" | "py" | "php" | "html" | "css" | "js" => name.cyan(), // source files
-                "zip" | "tar" | "gz" | "rar" | "7zip" => name.yellow(), // archives
-                "psd" | "svg" | "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" => name.magenta(), // images
-                "mp4" | "mkv" | "avi" | "mov" | "flv" | "wmv" => name.purple(), // videos
-                "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "pps" | "ppsx" => {
-                    name.bright_black()
-                }
-                _ => name.white(),
+/// Rows of directories whose only listed child is the directory on the next row
+fn single_child_chains(rows: &[(&tree::TreeEntry, Option<fs::FileType>)]) -> HashSet<usize> {
+    let mut children: HashMap<&Path, usize> = HashMap::new();
+    for (info, _) in rows {
+        if let Some(parent) = info.path.parent() {
+            *children.entry(parent).or_default() += 1;
+        }
+    }
+
+    rows.windows(2)
+        .enumerate()
+        .filter(|(_, pair)| {
+            let (dir, next) = (pair[0].0, pair[1].0);
+            dir.is_directory
+                && !dir.in_archive
+                && next.is_directory
+                && next.path.parent() == Some(dir.path.as_path())
+                && children.get(dir.path.as_path()) == Some(&1)
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Prints the entries as an aligned table, like `ls -l`; the header row is omitted in long format
+fn print_table(tree: &tree::Tree, args: &Args) -> io::Result<(usize, usize, u64)> {
+    let long = args.format == Format::Long;
+    let mut columns = args
+        .columns
+        .clone()
+        .unwrap_or_else(|| if long { LONG_COLUMNS } else { TABLE_COLUMNS }.to_vec());
+    // Without `--columns`, `--owner` adds the owner after the permissions, like `ls -l`
+    if args.columns.is_none() && args.owner {
+        let at = columns.iter().position(|&c| c == Column::Perms).map_or(0, |i| i + 1);
+        columns.insert(at, Column::Owner);
+    }
+
+    let header: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            match column {
+                Column::Name => "PATH",
+                Column::Size => "SIZE",
+                Column::Perms => "PERMS",
+                Column::Owner => "OWNER",
+                Column::Mtime => "MODIFIED",
             }
+            .to_string()
+        })
+        .collect();
+
+    let mut rows = if long { Vec::new() } else { vec![header] };
+    let (mut dir_count, mut file_count, mut total_size) = (0, 0, 0);
+    for (entry, info) in tree.entries.iter().zip(&tree.tree_info) {
+        if info.is_directory {
+            dir_count += 1;
         } else {
-            name.normal()
+            file_count += 1;
+        }
+        if let Some((dirs, files)) = info.collapsed {
+            dir_count += dirs;
+            file_count += files;
+        }
+        if info.depth == 1 {
+            total_size += info.size.unwrap_or(0);
+        }
+
+        let rel = entry.path().strip_prefix(&args.path).unwrap_or(entry.path());
+        rows.push(
+            columns
+                .iter()
+                .map(|column| match column {
+                    Column::Name if info.is_directory => format!("{}/", rel.display()),
+                    Column::Name => rel.display().to_string(),
+                    Column::Size => args.format_size(info.size.unwrap_or(0)),
+                    Column::Perms => {
+                        info.permissions.as_deref().unwrap_or_default().trim_end().to_string()
+                    }
+                    Column::Owner => {
+                        info.owner.as_deref().unwrap_or_default().trim_end().to_string()
+                    }
+                    Column::Mtime => info.modified.map(format::datetime).unwrap_or_default(),
+                })
+                .collect(),
+        );
+    }
+
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| rows.iter().map(|row| row[i].chars().count()).max().unwrap_or(0))
+        .collect();
+
+    let mut stdout = io::stdout();
+    for row in &rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(columns.iter().zip(&widths))
+            .map(|(cell, (column, &width))| match column {
+                Column::Size => format!("{cell:>width$}"),
+                _ => format!("{cell:<width$}"),
+            })
+            .collect();
+        writeln!(stdout, "{}", cells.join("  ").trim_end())?;
+    }
+
+    Ok((dir_count, file_count, total_size))
+}
+
+/// Share of `parent` taken by `size`, or `None` for an empty parent
+fn percent_of(size: u64, parent: u64) -> Option<f64> {
+    (parent > 0).then(|| size as f64 * 100.0 / parent as f64)
+}
+
+/// Branch of an entry: one indent per ancestor (`│   ` while that ancestor has
+/// siblings below, blank once it was the last), then the entry's own connector.
+/// `stack` holds the is-last flag of every level down to the entry itself.
+/// With `ascii`, the glyphs become `|   `, `` `-- `` and `|-- ` (same widths).
+fn compute_prefix(stack: &[bool], connector: &str, ascii: bool) -> String {
+    let (pipe, connector) = if ascii {
+        ("|   ", if connector == "└──" { "`--" } else { "|--" })
+    } else {
+        ("│   ", connector)
+    };
+    let mut prefix = String::new();
+    for &is_last in &stack[..stack.len().saturating_sub(1)] {
+        prefix.push_str(if is_last { "    " } else { pipe });
+    }
+    format!("{prefix}{connector} ")
+}
+
+/// Dims a permission string, or with `--color-perms` colors each flag by kind
+fn style_permissions(permissions: &str, by_field: bool) -> String {
+    if !by_field {
+        return permissions.dimmed().to_string();
+    }
+    permissions
+        .chars()
+        .map(|c| match perm_color(c) {
+            Some(color) => c.to_string().color(color).to_string(),
+            None => c.to_string().dimmed().to_string(),
+        })
+        .collect()
+}
+
+/// Color of one permission flag; `-` and padding stay dimmed
+fn perm_color(flag: char) -> Option<colored::Color> {
+    match flag {
+        'd' => Some(colored::Color::Blue),
+        'r' => Some(colored::Color::Yellow),
+        'w' => Some(colored::Color::Red),
+        'x' | 's' | 't' => Some(colored::Color::Green),
+        _ => None,
+    }
+}
+
+/// Joins the cells selected with `--columns`, in the requested order;
+/// `permissions` comes already styled
+fn render_columns(
+    columns: &[Column],
+    permissions: &str,
+    owner: &str,
+    size: &str,
+    modified: Option<SystemTime>,
+    name: &str,
+) -> String {
+    columns
+        .iter()
+        .map(|column| match column {
+            Column::Perms => permissions.trim_end().to_string(),
+            Column::Owner => owner.trim_end().dimmed().to_string(),
+            Column::Size => format!("{:>10}", size).dimmed().to_string(),
+            Column::Mtime => format!("{:<16}", modified.map(format::datetime).unwrap_or_default())
+                .dimmed()
+                .to_string(),
+            Column::Name => name.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+#[inline]
+fn style_entry_name(
+    info: &tree::TreeEntry,
+    file_type: Option<fs::FileType>,
+    ls_colors: &LsColors,
+) -> String {
+    let path = info.path.as_path();
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+    // Default color based on type/extension, from what the scan already knows
+    let mut styled = if info.is_directory {
+        name.blue().bold()
+    } else if info.is_executable {
+        name.green()
+    } else if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        let ext = ext.to_lowercase();
+        match EXTENSION_COLORS.iter().find(|(_, exts, _)| exts.contains(&ext.as_str())) {
+            Some(&(_, _, color)) => name.color(color),
+            None => name.white(),
         }
     } else {
         name.normal()
     };
 
     // LS colors always take precedence
-    if let Some(ls_style) = ls_colors.style_for_path(path) {
+    if let Some(ls_style) = ls_colors.style_for(&ScannedPath { path, file_type }) {
         let mut ls_styled = styled.normal();
+        let mut indexed = None;
 
         if let Some(fg) = ls_style.foreground {
-            ls_styled = ls_styled.color(ls_color_to_colored(fg));
+            match ls_color_to_term(fg, supports_truecolor()) {
+                TermColor::Basic(color) => ls_styled = ls_styled.color(color),
+                TermColor::Indexed(n) => indexed = Some(n),
+            }
         }
 
         if ls_style.font_style.bold {
@@ -194,32 +641,132 @@ fn style_entry_name(path: &std::path::Path, ls_colors: &LsColors) -> colored::Co
         if ls_style.font_style.underline {
             ls_styled = ls_styled.underline();
         }
+
+        // `colored` has no 256-color palette, so indexed colors are emitted by hand
+        if let Some(n) = indexed
+            && colored::control::SHOULD_COLORIZE.should_colorize()
+        {
+            return format!("\x1B[38;5;{n}m{ls_styled}\x1B[0m");
+        }
         styled = ls_styled;
     }
 
-    styled
+    styled.to_string()
 }
 
-// Cross-platform function to check if a file is executable
-#[inline]
-fn is_executable(path: &std::path::Path, metadata: &fs::Metadata) -> bool {
-    #[cfg(unix)]
-    {
-        let _ = path;
-        use std::os::unix::fs::PermissionsExt;
-        metadata.permissions().mode() & 0o111 != 0
+/// Dims ignored entries and colors untracked ones green, keeping directories bold
+fn style_git_status(info: &tree::TreeEntry, status: git::FileStatus) -> String {
+    let name = info.path.file_name().unwrap_or_default().to_string_lossy();
+    let styled = match status {
+        git::FileStatus::Ignored => name.dimmed(),
+        git::FileStatus::Untracked => name.green(),
+    };
+    if info.is_directory { styled.bold().to_string() } else { styled.to_string() }
+}
+
+/// Prints the color/icon conventions used by `style_entry_name`
+fn print_legend(args: &Args) -> io::Result<()> {
+    let icon = |name: &str, is_dir: bool| {
+        if args.shows_icon(is_dir) {
+            format!("{} ", icons::get_icon_for_path(Path::new(name), is_dir, args.icon_set))
+        } else {
+            String::new()
+        }
+    };
+
+    let mut out = io::stdout();
+    writeln!(out, "\nLegend:")?;
+    writeln!(out, "  {}{}", icon("", true), "directory".blue().bold())?;
+    writeln!(out, "  {}{}", icon("", false), "executable".green())?;
+    for &(category, exts, color) in EXTENSION_COLORS {
+        writeln!(
+            out,
+            "  {}{} {}",
+            icon(&format!("_.{}", exts[0]), false),
+            format!("{category:<10}").color(color),
+            exts.join(", ").dimmed()
+        )?;
     }
+    writeln!(out, "  {}{}", icon("", false), "other".white())?;
+    writeln!(out, "  {}", "LS_COLORS, when set, takes precedence".dimmed())
+}
 
-    #[cfg(windows)]
-    {
-        let _ = metadata;
-        path.extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| matches!(ext.to_lowercase().as_str(), "exe" | "bat" | "cmd"))
-            .unwrap_or(false)
+/// A path whose file type is known from the walk; LS_COLORS only reads its
+/// metadata for indicators that need the mode (e.g. `ex`, `su`)
+struct ScannedPath<'a> {
+    path: &'a Path,
+    file_type: Option<fs::FileType>,
+}
+
+impl Colorable for ScannedPath<'_> {
+    fn path(&self) -> PathBuf {
+        self.path.to_path_buf()
+    }
+
+    fn file_name(&self) -> OsString {
+        self.path.file_name().unwrap_or(self.path.as_os_str()).to_os_string()
+    }
+
+    fn file_type(&self) -> Option<fs::FileType> {
+        self.file_type
+    }
+
+    fn metadata(&self) -> Option<fs::Metadata> {
+        self.path.symlink_metadata().ok()
     }
 }
 
+/// A foreground color for the classic view, including the 256-color palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TermColor {
+    Basic(colored::Color),
+    Indexed(u8),
+}
+
+/// Whether the terminal advertises 24-bit color through `COLORTERM`.
+///
+/// Whether to color at all (`CLICOLOR`, `CLICOLOR_FORCE`, `NO_COLOR`) is left to `colored`.
+fn supports_truecolor() -> bool {
+    std::env::var("COLORTERM").is_ok_and(|v| v == "truecolor" || v == "24bit")
+}
+
+#[inline]
+fn ls_color_to_term(ls_color: lscolors::Color, truecolor: bool) -> TermColor {
+    use lscolors::Color as LsColor;
+    match ls_color {
+        LsColor::Fixed(n) => TermColor::Indexed(n),
+        LsColor::RGB(r, g, b) if !truecolor => TermColor::Indexed(rgb_to_ansi256(r, g, b)),
+        other => TermColor::Basic(ls_color_to_colored(other)),
+    }
+}
+
+/// Maps an RGB color to the nearest entry of the xterm 256-color cube or gray ramp
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    let nearest_level = |v: u8| {
+        (0..LEVELS.len()).min_by_key(|&i| (i32::from(v) - i32::from(LEVELS[i])).abs()).unwrap_or(0)
+    };
+    let distance = |(r2, g2, b2): (u8, u8, u8)| {
+        [(r, r2), (g, g2), (b, b2)]
+            .iter()
+            .map(|&(a, b)| (i32::from(a) - i32::from(b)).pow(2))
+            .sum::<i32>()
+    };
+
+    let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+    let cube_distance = distance((LEVELS[ri], LEVELS[gi], LEVELS[bi]));
+
+    let average = (u32::from(r) + u32::from(g) + u32::from(b)) / 3;
+    let gray_step = (average.saturating_sub(8) / 10).min(23);
+    let gray_level = (8 + gray_step * 10) as u8;
+    let gray_distance = distance((gray_level, gray_level, gray_level));
+
+    if gray_distance < cube_distance { 232 + gray_step as u8 } else { cube_index as u8 }
+}
+
+/// Named and RGB colors; fixed (indexed) colors never get here, see [`ls_color_to_term`]
 #[inline]
 fn ls_color_to_colored(ls_color: lscolors::Color) -> colored::Color {
     use lscolors::Color as LsColor;
@@ -240,18 +787,138 @@ fn ls_color_to_colored(ls_color: lscolors::Color) -> colored::Color {
         LsColor::BrightMagenta => colored::Color::BrightMagenta,
         LsColor::BrightCyan => colored::Color::BrightCyan,
         LsColor::BrightWhite => colored::Color::BrightWhite,
-        LsColor::Fixed(_) => colored::Color::White,
-        // Fallback for fixed colors
         LsColor::RGB(r, g, b) => colored::Color::TrueColor { r, g, b },
+        LsColor::Fixed(n) => unreachable!("indexed color {n} is written as a raw escape"),
     }
 }
 
 // Create a clickable hyperlink (if supported by the terminal)
-fn make_hyperlink(path: &std::path::Path, styled_name: colored::ColoredString) -> String {
-    if let Ok(abs_path) = fs::canonicalize(path) {
-        if let Ok(url) = Url::from_file_path(abs_path) {
-            return format!("\x1B]8;;{url}\x07{styled_name}\x1B]8;;\x07");
-        }
+fn make_hyperlink(path: &std::path::Path, styled_name: String) -> String {
+    if let Ok(abs_path) = fs::canonicalize(path)
+        && let Ok(url) = Url::from_file_path(abs_path)
+    {
+        return format!("\x1B]8;;{url}\x07{styled_name}\x1B]8;;\x07");
+    }
+    styled_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind};
+    use notify::{Event, EventKind, NullWatcher};
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_compute_prefix() {
+        assert_eq!(compute_prefix(&[false], "├──", false), "├── ");
+        assert_eq!(compute_prefix(&[true], "└──", false), "└── ");
+        assert_eq!(compute_prefix(&[false, true], "└──", false), "│   └── ");
+        assert_eq!(compute_prefix(&[true, false], "├──", false), "    ├── ");
+        assert_eq!(compute_prefix(&[false, true, false, true], "└──", false), "│       │   └── ");
+        assert_eq!(compute_prefix(&[true, true, true], "└──", false), "        └── ");
+        // An empty stack has no ancestors to indent for
+        assert_eq!(compute_prefix(&[], "├──", false), "├── ");
+
+        // ASCII glyphs keep the same widths
+        assert_eq!(compute_prefix(&[false, true], "└──", true), "|   `-- ");
+        assert_eq!(compute_prefix(&[true, false], "├──", true), "    |-- ");
+    }
+
+    #[test]
+    fn test_watch_rerenders_on_file_creation() {
+        let (tx, rx) = channel();
+        let watcher = tree::TreeWatcher::new(NullWatcher, rx);
+
+        // Access events come from scanning itself and must not trigger a render
+        tx.send(Ok(Event::new(EventKind::Access(AccessKind::Any)))).unwrap();
+        tx.send(Ok(Event::new(EventKind::Create(CreateKind::File)).add_path("new.txt".into())))
+            .unwrap();
+        drop(tx);
+
+        let mut renders = 0;
+        watch(&watcher, || {
+            renders += 1;
+            Ok(())
+        })
+        .unwrap();
+
+        // Initial render plus one for the creation event
+        assert_eq!(renders, 2);
+    }
+
+    #[test]
+    fn test_percent_of_empty_parent() {
+        assert_eq!(percent_of(0, 0), None);
+        assert_eq!(percent_of(1, 4), Some(25.0));
+    }
+
+    #[test]
+    fn test_fixed_color_maps_to_indexed() {
+        assert_eq!(ls_color_to_term(lscolors::Color::Fixed(42), true), TermColor::Indexed(42));
+        assert_eq!(ls_color_to_term(lscolors::Color::Fixed(42), false), TermColor::Indexed(42));
+    }
+
+    #[test]
+    fn test_rgb_downgrades_without_truecolor() {
+        let rgb = lscolors::Color::RGB(255, 0, 0);
+        assert_eq!(
+            ls_color_to_term(rgb, true),
+            TermColor::Basic(colored::Color::TrueColor { r: 255, g: 0, b: 0 })
+        );
+        assert_eq!(ls_color_to_term(rgb, false), TermColor::Indexed(196));
+
+        // Grays land on the gray ramp rather than the color cube
+        assert_eq!(rgb_to_ansi256(128, 128, 128), 244);
+        assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+        assert_eq!(rgb_to_ansi256(255, 255, 255), 231);
+    }
+
+    #[test]
+    fn test_style_entry_name_uses_scanned_metadata() {
+        // None of these paths exist: the styling must rely on the scanned entry alone
+        let ls_colors = LsColors::empty();
+        let style = |path: &str, is_directory, is_executable| {
+            let info = tree::TreeEntry {
+                path: format!("/wisu-missing/{path}").into(),
+                is_directory,
+                is_executable,
+                ..Default::default()
+            };
+            style_entry_name(&info, None, &ls_colors)
+        };
+
+        assert_eq!(style("src", true, false), "src".blue().bold().to_string());
+        assert_eq!(style("run.sh", false, true), "run.sh".green().to_string());
+        assert_eq!(style("main.rs", false, false), "main.rs".cyan().to_string());
+        assert_eq!(style("notes.txt", false, false), "notes.txt".white().to_string());
+        assert_eq!(style("Makefile", false, false), "Makefile".normal().to_string());
+    }
+
+    #[test]
+    fn test_color_perms_by_field() {
+        use colored::Color::{Blue, Green, Red, Yellow};
+
+        let perms = format!("d{}", format::format_permissions(0o754));
+        let colors: Vec<_> = perms.chars().map(perm_color).collect();
+        assert_eq!(
+            colors,
+            [
+                Some(Blue),
+                Some(Yellow),
+                Some(Red),
+                Some(Green),
+                Some(Yellow),
+                None,
+                Some(Green),
+                Some(Yellow),
+                None,
+                None
+            ]
+        );
+
+        let expected = format!("{}{}{}", "r".yellow(), "w".red(), "-".dimmed());
+        assert_eq!(style_permissions("rw-", true), expected);
+        assert_eq!(style_permissions("rw-", false), "rw-".dimmed().to_string());
     }
-    styled_name.to_string()
 }
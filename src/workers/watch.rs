@@ -0,0 +1,32 @@
+//! `--watch` for exports: a full snapshot up front, then a fresh one after every
+//! debounced filesystem change under `args.path`.
+//!
+//! Every snapshot is flushed before waiting again. NDJSON snapshots open with a
+//! `{"snapshot":N}` record. Ctrl-C (or SIGTERM) lets the current snapshot finish
+//! and then ends the watch; a second Ctrl-C exits right away.
+
+use crate::app::Args;
+use crate::common::tree::{self, Tree};
+use crate::workers::export;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+/// Exports once, then again after each change until interrupted or the watcher goes away
+pub fn run(args: &Args) -> anyhow::Result<()> {
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM] {
+        // Registered first, so it only fires for a signal arriving once `stop` is set
+        signal_hook::flag::register_conditional_shutdown(signal, 130, Arc::clone(&stop))?;
+        signal_hook::flag::register(signal, Arc::clone(&stop))?;
+    }
+
+    let watcher = Tree::create_watcher(args)?;
+    let mut snapshot = 1;
+    export::export_snapshot(args, Some(snapshot))?;
+    while watcher.wait_for_debounced_change_until(tree::WATCH_DEBOUNCE, &stop) {
+        snapshot += 1;
+        export::export_snapshot(args, Some(snapshot))?;
+    }
+    Ok(())
+}
@@ -1,13 +1,23 @@
+use assert_cmd::cargo_bin;
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
 use std::fs;
 use std::process::Command;
 use tempfile::tempdir;
 
+/// `wisu` with the global config layer pointed at an empty directory, so the
+/// developer's own `~/.config/wisu/wisu.toml` (or `%APPDATA%`) never leaks in
+fn wisu() -> Command {
+    let no_config = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("no-global-config");
+    let mut cmd = Command::new(cargo_bin!("wisu"));
+    cmd.env("XDG_CONFIG_HOME", &no_config).env("HOME", &no_config).env("APPDATA", &no_config);
+    cmd
+}
+
 /// Tests behavior on a nonexistent path
 #[test]
 fn test_nonexistent_path() -> Result<(), Box<dyn std::error::Error>> {
-    let mut cmd = Command::cargo_bin("wisu")?;
+    let mut cmd = wisu();
     cmd.arg("nonexistent/path/for/testing");
 
     // Should fail with an error message
@@ -23,7 +33,7 @@ fn test_simple_view() -> Result<(), Box<dyn std::error::Error>> {
     fs::create_dir(temp_dir.path().join("dir1"))?;
     fs::File::create(temp_dir.path().join("dir1/b.txt"))?;
 
-    let mut cmd = Command::cargo_bin("wisu")?;
+    let mut cmd = wisu();
     cmd.arg(temp_dir.path());
 
     // Should include all files and directories
@@ -42,12 +52,12 @@ fn test_all_flag() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join(".hidden"))?;
 
     // Without -a, hidden file should not appear
-    let mut cmd_no_all = Command::cargo_bin("wisu")?;
+    let mut cmd_no_all = wisu();
     cmd_no_all.arg(temp_dir.path());
     cmd_no_all.assert().success().stdout(predicate::str::contains(".hidden").not());
 
     // With -a, hidden file should appear
-    let mut cmd_with_all = Command::cargo_bin("wisu")?;
+    let mut cmd_with_all = wisu();
     cmd_with_all.arg("-a").arg(temp_dir.path());
     cmd_with_all.assert().success().stdout(predicate::str::contains(".hidden"));
     Ok(())
@@ -60,7 +70,7 @@ fn test_depth_flag() -> Result<(), Box<dyn std::error::Error>> {
     fs::create_dir(temp_dir.path().join("dir1"))?;
     fs::File::create(temp_dir.path().join("dir1/b.txt"))?;
 
-    let mut cmd = Command::cargo_bin("wisu")?;
+    let mut cmd = wisu();
     cmd.arg("-L").arg("1").arg(temp_dir.path());
 
     // Only first-level directory should appear
@@ -84,7 +94,7 @@ fn test_permissions_flag() -> Result<(), Box<dyn std::error::Error>> {
     let perms = fs::Permissions::from_mode(0o550);
     fs::set_permissions(&file_path, perms)?;
 
-    let mut cmd = Command::cargo_bin("wisu")?;
+    let mut cmd = wisu();
     cmd.arg("-p").arg(temp_dir.path());
 
     // Check permissions string
@@ -100,7 +110,7 @@ fn test_sort_by_name() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join("apple.txt"))?;
     fs::File::create(temp_dir.path().join("banana.txt"))?;
 
-    let mut cmd = Command::cargo_bin("wisu")?;
+    let mut cmd = wisu();
     cmd.arg("--sort").arg("name").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -120,7 +130,7 @@ fn test_dirs_first_sorting() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join("aaa_file.txt"))?;
     fs::create_dir(temp_dir.path().join("zzz_dir"))?;
 
-    let mut cmd = Command::cargo_bin("wisu")?;
+    let mut cmd = wisu();
     cmd.arg("--dirs-first").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -140,7 +150,7 @@ fn test_natural_sorting() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join("file10.txt"))?;
     fs::File::create(temp_dir.path().join("file2.txt"))?;
 
-    let mut cmd = Command::cargo_bin("wisu")?;
+    let mut cmd = wisu();
     cmd.arg("--natural-sort").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -160,7 +170,7 @@ fn test_reverse_sorting() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join("apple.txt"))?;
     fs::File::create(temp_dir.path().join("zebra.txt"))?;
 
-    let mut cmd = Command::cargo_bin("wisu")?;
+    let mut cmd = wisu();
     cmd.arg("--reverse").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -179,7 +189,7 @@ fn test_case_sensitive_sorting() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join("Apple.txt"))?;
     fs::File::create(temp_dir.path().join("banana.txt"))?;
 
-    let mut cmd = Command::cargo_bin("wisu")?;
+    let mut cmd = wisu();
     cmd.arg("--case-sensitive").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -199,7 +209,7 @@ fn test_sort_by_extension() -> Result<(), Box<dyn std::error::Error>> {
     fs::File::create(temp_dir.path().join("file.aaa"))?;
     fs::File::create(temp_dir.path().join("file.bbb"))?;
 
-    let mut cmd = Command::cargo_bin("wisu")?;
+    let mut cmd = wisu();
     cmd.arg("--sort").arg("extension").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -220,7 +230,7 @@ fn test_default_sort_order() -> Result<(), Box<dyn std::error::Error>> {
     fs::write(temp_dir.path().join("Upper.txt"), "A")?;
     fs::write(temp_dir.path().join("lower.txt"), "a")?;
 
-    let mut cmd = Command::cargo_bin("wisu")?;
+    let mut cmd = wisu();
     cmd.arg("--case-sensitive").arg(temp_dir.path());
 
     let output = cmd.output()?;
@@ -232,3 +242,1403 @@ fn test_default_sort_order() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Tests the --legend flag lists every color category
+#[test]
+fn test_legend_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::File::create(temp_dir.path().join("a.txt"))?;
+
+    let mut cmd = wisu();
+    cmd.arg("--legend").arg(temp_dir.path());
+
+    let output = cmd.output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("Legend:"));
+    for category in
+        ["directory", "executable", "source", "archive", "image", "video", "document", "other"]
+    {
+        assert!(stdout.contains(category), "missing legend category {category}");
+    }
+
+    Ok(())
+}
+
+/// Tests --columns renders the requested columns in the requested order
+#[test]
+fn test_columns_order() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("data.bin"), "12345")?;
+
+    let mut cmd = wisu();
+    cmd.arg("--columns").arg("size,name").arg(temp_dir.path());
+    let stdout = String::from_utf8(cmd.output()?.stdout)?;
+    let line = stdout.lines().find(|l| l.contains("data.bin")).unwrap();
+    assert!(line.find("5 B").unwrap() < line.find("data.bin").unwrap());
+
+    let mut cmd = wisu();
+    cmd.arg("--columns").arg("name,size").arg(temp_dir.path());
+    let stdout = String::from_utf8(cmd.output()?.stdout)?;
+    let line = stdout.lines().find(|l| l.contains("data.bin")).unwrap();
+    assert!(line.find("data.bin").unwrap() < line.find("5 B").unwrap());
+
+    Ok(())
+}
+
+/// Tests an unknown column name is rejected with a clear error
+#[test]
+fn test_columns_unknown() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+
+    let mut cmd = wisu();
+    cmd.arg("--columns").arg("size,bogus").arg(temp_dir.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'bogus'"))
+        .stderr(predicate::str::contains("perms, owner, size, mtime, name"));
+
+    Ok(())
+}
+
+/// Tests that --ignore-file excludes matching entries and stacks
+#[test]
+fn test_ignore_file() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("build"))?;
+    fs::File::create(temp_dir.path().join("build/out.o"))?;
+    fs::File::create(temp_dir.path().join("notes.tmp"))?;
+    fs::File::create(temp_dir.path().join("keep.txt"))?;
+
+    let lists = tempdir()?;
+    fs::write(lists.path().join("dirs.ignore"), "build/\n")?;
+    fs::write(lists.path().join("files.ignore"), "*.tmp\n")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path())
+        .arg("--ignore-file")
+        .arg(lists.path().join("dirs.ignore"))
+        .arg("--ignore-file")
+        .arg(lists.path().join("files.ignore"));
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("keep.txt"))
+        .stdout(predicate::str::contains("build").not())
+        .stdout(predicate::str::contains("out.o").not())
+        .stdout(predicate::str::contains("notes.tmp").not());
+    Ok(())
+}
+
+/// Tests that an invalid sort value in wisu.toml is reported instead of ignored
+#[test]
+fn test_config_invalid_sort() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("wisu.toml"), "sort = \"bogus\"\n")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path());
+
+    let valid = "valid options: name, size, accessed, created, modified, extension";
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid sort value 'bogus'"))
+        .stderr(predicate::str::contains(valid));
+    Ok(())
+}
+
+/// Tests that a partial config file is applied
+#[test]
+fn test_config_partial() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("wisu.toml"), "all = true\n")?;
+    fs::File::create(temp_dir.path().join(".hidden"))?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path());
+
+    cmd.assert().success().stdout(predicate::str::contains(".hidden"));
+    Ok(())
+}
+
+/// Tests that --size-in forces every size into one unit
+#[test]
+fn test_size_in_unit() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    // Sparse 1 GiB file
+    fs::File::create(temp_dir.path().join("big.bin"))?.set_len(1024 * 1024 * 1024)?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("-s").arg("--size-in").arg("MiB");
+
+    cmd.assert().success().stdout(predicate::str::contains("big.bin (1024.0 MiB)"));
+    Ok(())
+}
+
+/// Tests that --no-connector indents by depth without connector glyphs
+#[test]
+fn test_no_connector() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("dir1/dir2"))?;
+    fs::File::create(temp_dir.path().join("dir1/dir2/c.txt"))?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--no-connector");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\n    dir1\n        dir2\n            c.txt\n"))
+        .stdout(predicate::str::contains("├").not())
+        .stdout(predicate::str::contains("└").not())
+        .stdout(predicate::str::contains("│").not());
+    Ok(())
+}
+
+/// Tests that --hide-config omits the auto-discovered wisu.toml
+#[test]
+fn test_hide_config() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("wisu.toml"), "all = true\n")?;
+    fs::File::create(temp_dir.path().join("a.txt"))?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path());
+    cmd.assert().success().stdout(predicate::str::contains("wisu.toml"));
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--hide-config");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("a.txt"))
+        .stdout(predicate::str::contains("wisu.toml").not());
+    Ok(())
+}
+
+/// Tests that --dereference-args only changes how a symlinked root is displayed
+#[cfg(unix)]
+#[test]
+fn test_dereference_args() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = tempdir()?;
+    let real = temp_dir.path().join("real");
+    fs::create_dir_all(real.join("sub"))?;
+    fs::create_dir(temp_dir.path().join("other"))?;
+    fs::File::create(real.join("sub/inside.txt"))?;
+    fs::File::create(temp_dir.path().join("other/outside.txt"))?;
+    symlink(temp_dir.path().join("other"), real.join("link"))?;
+    symlink(&real, temp_dir.path().join("root"))?;
+
+    let link = temp_dir.path().join("root");
+    let resolved = real.canonicalize()?.display().to_string();
+    let run = |extra: &[&str]| -> Result<String, Box<dyn std::error::Error>> {
+        let output = wisu().arg(&link).args(extra).output()?;
+        assert!(output.status.success());
+        Ok(String::from_utf8(output.stdout)?)
+    };
+
+    // Both runs list the target's contents, without following the inner link
+    let plain = run(&[])?;
+    let dereferenced = run(&["--dereference-args"])?;
+    for stdout in [&plain, &dereferenced] {
+        assert!(stdout.contains("inside.txt") && stdout.contains("link"));
+        assert!(!stdout.contains("outside.txt"));
+    }
+
+    // Only the flag shows the root by its resolved path
+    assert!(
+        dereferenced.lines().find(|l| !l.is_empty()).is_some_and(|root| root.contains(&resolved))
+    );
+    assert!(!plain.contains(&resolved));
+    assert!(plain.lines().find(|l| !l.is_empty()).is_some_and(|root| root.ends_with("root")));
+    Ok(())
+}
+
+/// Tests that --no-recursion lists only the root's direct children, flat
+#[test]
+fn test_no_recursion() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("dir1/nested"))?;
+    fs::File::create(temp_dir.path().join("dir1/deep.txt"))?;
+    fs::File::create(temp_dir.path().join("top.txt"))?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--no-recursion");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\ndir1\ntop.txt\n"))
+        .stdout(predicate::str::contains("nested").not())
+        .stdout(predicate::str::contains("deep.txt").not())
+        .stdout(predicate::str::contains("└").not());
+    Ok(())
+}
+
+/// Tests that --percent shares within a directory sum to about 100%
+#[test]
+fn test_percent_of_parent() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("dir1"))?;
+    for (name, len) in [("a.txt", 100), ("b.txt", 250), ("c.txt", 650)] {
+        fs::write(temp_dir.path().join("dir1").join(name), vec![b'x'; len])?;
+    }
+    fs::File::create(temp_dir.path().join("empty"))?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("-x").arg("--percent");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output)?;
+
+    let percents: Vec<f64> = stdout
+        .lines()
+        .filter(|line| line.contains(".txt"))
+        .filter_map(|line| {
+            let (_, rest) = line.split_once('(')?;
+            rest.split_once("%)")?.0.parse().ok()
+        })
+        .collect();
+    assert_eq!(percents, [10.0, 25.0, 65.0]);
+    assert!((percents.iter().sum::<f64>() - 100.0).abs() <= 1.0);
+    assert!(stdout.contains("dir1  [ 1000 B (100%)"));
+    Ok(())
+}
+
+/// Tests that `dir` and `dir/` export identically
+#[test]
+fn test_trailing_slash_root_export() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("proj/sub"))?;
+    fs::File::create(temp_dir.path().join("proj/sub/a.txt"))?;
+
+    let mut exports = Vec::new();
+    for (out_dir, root) in [("out1", "proj"), ("out2", "proj/")] {
+        let cwd = temp_dir.path().join(out_dir);
+        fs::create_dir(&cwd)?;
+        let mut cmd = wisu();
+        cmd.current_dir(&cwd).arg(format!("../{root}")).arg("-o").arg("json");
+        cmd.assert().success();
+        let mut export: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(cwd.join("export.json"))?)?;
+        // Timing differs between runs
+        export.as_object_mut().unwrap().remove("scan");
+        exports.push(export.to_string());
+    }
+
+    assert!(exports[0].contains("proj/sub/a.txt"));
+    assert_eq!(exports[0], exports[1]);
+    Ok(())
+}
+
+/// Tests that --since-commit only shows files changed after the given ref
+#[test]
+fn test_since_commit() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    fs::create_dir(root.join("src"))?;
+    fs::write(root.join("src/changed.rs"), "old")?;
+    fs::write(root.join("src/same.rs"), "same")?;
+    fs::write(root.join("untouched.md"), "same")?;
+
+    // Commit everything, then modify a single file
+    let repo = git2::Repository::init(root)?;
+    let mut index = repo.index()?;
+    index.add_all(["*"], git2::IndexAddOption::DEFAULT, None)?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = git2::Signature::now("wisu", "wisu@example.com")?;
+    repo.commit(Some("HEAD"), &signature, &signature, "initial", &tree, &[])?;
+    fs::write(root.join("src/changed.rs"), "new")?;
+
+    let mut cmd = wisu();
+    cmd.arg(root).arg("--since-commit").arg("HEAD");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("src"))
+        .stdout(predicate::str::contains("changed.rs"))
+        .stdout(predicate::str::contains("same.rs").not())
+        .stdout(predicate::str::contains("untouched.md").not());
+    Ok(())
+}
+
+/// Tests the aligned --format table output with headers
+#[test]
+fn test_format_table() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("a.txt"), "hello")?;
+    fs::create_dir(temp_dir.path().join("dir1"))?;
+    fs::write(temp_dir.path().join("dir1/b.txt"), "abc")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--format").arg("table").arg("--columns").arg("name,size");
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "PATH        SIZE\na.txt        5 B\ndir1/        3 B\ndir1/b.txt   3 B\n",
+    ));
+    Ok(())
+}
+
+/// Tests that --format table shows perms and mtime columns by default
+#[test]
+fn test_format_table_default_columns() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("a.txt"), "hello")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--format").arg("table");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"PATH\s+SIZE\s+PERMS\s+MODIFIED\n")?)
+        .stdout(predicate::str::is_match(r"a\.txt\s+5 B\s+\S+\s+\d{4}-\d{2}-\d{2} \d{2}:\d{2}\n")?);
+    Ok(())
+}
+
+/// Tests that --checksum-manifest reports files changed since --checksums wrote the manifest
+#[test]
+fn test_checksum_manifest() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let out_dir = tempdir()?;
+    let manifest = out_dir.path().join("sums.txt");
+    fs::write(temp_dir.path().join("a.txt"), "one")?;
+    fs::write(temp_dir.path().join("b.txt"), "two")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--checksums").arg(&manifest);
+    cmd.assert().success();
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--checksum-manifest").arg(&manifest);
+    cmd.assert().success().stdout(predicate::str::contains("0 added, 0 removed, 0 modified"));
+
+    fs::write(temp_dir.path().join("a.txt"), "changed")?;
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--checksum-manifest").arg(&manifest);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("modified a.txt"))
+        .stdout(predicate::str::contains("b.txt").not());
+    Ok(())
+}
+
+/// Tests that --long prints permissions, size, mtime and path on one line per entry
+#[test]
+fn test_long_format() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("sub"))?;
+    fs::write(temp_dir.path().join("a.txt"), "hello")?;
+    fs::write(temp_dir.path().join("sub").join("b.txt"), "hi")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--long");
+
+    let date = r"\d{4}-\d{2}-\d{2} \d{2}:\d{2}";
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(format!(r"(?m)^\S+\s+5 B\s+{date}\s+a\.txt$"))?)
+        .stdout(predicate::str::is_match(format!(r"(?m)^\S+\s+.+\s+{date}\s+sub/$"))?)
+        .stdout(predicate::str::is_match(format!(r"(?m)^\S+\s+2 B\s+{date}\s+sub/b\.txt$"))?)
+        .stdout(predicate::str::contains("PATH").not())
+        .stdout(predicate::str::contains("──").not());
+    Ok(())
+}
+
+/// Tests that WISU_PATH replaces the default root but not an explicit path
+#[test]
+fn test_wisu_path_env() -> Result<(), Box<dyn std::error::Error>> {
+    let env_dir = tempdir()?;
+    let cli_dir = tempdir()?;
+    fs::File::create(env_dir.path().join("from_env.txt"))?;
+    fs::File::create(cli_dir.path().join("from_cli.txt"))?;
+
+    let mut cmd = wisu();
+    cmd.env("WISU_PATH", env_dir.path()).current_dir(cli_dir.path());
+    cmd.assert().success().stdout(predicate::str::contains("from_env.txt"));
+
+    let mut cmd = wisu();
+    cmd.env("WISU_PATH", env_dir.path()).arg(cli_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("from_cli.txt"))
+        .stdout(predicate::str::contains("from_env.txt").not());
+    Ok(())
+}
+
+/// Tests that JSON and XML exports carry the scan duration and throughput
+#[test]
+fn test_export_scan_stats() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let out_dir = tempdir()?;
+    for i in 0..20 {
+        fs::write(temp_dir.path().join(format!("f{i}.txt")), "x")?;
+    }
+
+    let mut cmd = wisu();
+    cmd.current_dir(out_dir.path()).arg(temp_dir.path()).arg("-o").arg("json");
+    cmd.assert().success();
+
+    let export: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(out_dir.path().join("export.json"))?)?;
+    let scan = &export["scan"];
+    assert_eq!(scan["entries"], 20);
+    assert!(scan["duration_ms"].as_f64().unwrap() > 0.0);
+    assert!(scan["entries_per_sec"].as_f64().unwrap() > 0.0);
+
+    let mut cmd = wisu();
+    cmd.current_dir(out_dir.path()).arg(temp_dir.path()).arg("-o").arg("xml");
+    cmd.assert().success();
+    let xml = fs::read_to_string(out_dir.path().join("export.xml"))?;
+    assert!(xml.contains("<scan><entries>20</entries><duration_ms>"), "{xml}");
+    assert!(xml.trim_end().ends_with("</scan></ExportNode>"), "{xml}");
+    Ok(())
+}
+
+/// Tests that --max-size-per-dir folds large subtrees but keeps them in the totals
+#[test]
+fn test_max_size_per_dir() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("big/sub"))?;
+    fs::create_dir(temp_dir.path().join("small"))?;
+    for i in 0..3 {
+        fs::write(temp_dir.path().join(format!("big/sub/f{i}.bin")), [0u8; 1000])?;
+    }
+    fs::write(temp_dir.path().join("small/s.txt"), "tiny")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--max-size-per-dir").arg("2K");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("big  [ 2.9 KiB, collapsed ]"))
+        .stdout(predicate::str::contains("f0.bin").not())
+        .stdout(predicate::str::contains("sub").not())
+        .stdout(predicate::str::contains("s.txt"))
+        .stdout(predicate::str::contains("3 directories, 4 files"));
+    Ok(())
+}
+
+/// Tests that --ignore-symlinks drops symlinks from the listing and the counts
+#[cfg(unix)]
+#[test]
+fn test_ignore_symlinks() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("dir"))?;
+    fs::write(temp_dir.path().join("real.txt"), "data")?;
+    symlink(temp_dir.path().join("real.txt"), temp_dir.path().join("link.txt"))?;
+    symlink(temp_dir.path().join("dir"), temp_dir.path().join("dir_link"))?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path());
+    cmd.assert().success().stdout(predicate::str::contains("link.txt"));
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--ignore-symlinks");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("real.txt"))
+        .stdout(predicate::str::contains("link.txt").not())
+        .stdout(predicate::str::contains("dir_link").not())
+        .stdout(predicate::str::contains("1 directories, 1 files"));
+    Ok(())
+}
+
+/// Tests that --root-only-stats prints just the root line with recursive totals
+#[test]
+fn test_root_only_stats() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("sub/deep"))?;
+    fs::write(temp_dir.path().join("sub/deep/a.txt"), [0u8; 100])?;
+    fs::write(temp_dir.path().join("b.txt"), [0u8; 50])?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--root-only-stats");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("( 150 B  2 dirs, 2 files )"))
+        .stdout(predicate::str::contains("150 B, 2 directories, 2 files"))
+        .stdout(predicate::str::contains("a.txt").not())
+        .stdout(predicate::str::contains("sub").not());
+    Ok(())
+}
+
+/// Tests that --into-archives lists zip contents under the archive without counting them
+#[test]
+fn test_into_archives() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    let temp_dir = tempdir()?;
+    let mut zip = zip::ZipWriter::new(fs::File::create(temp_dir.path().join("bundle.zip"))?);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    zip.start_file("inner/packed.txt", options)?;
+    zip.write_all(b"hello")?;
+    zip.finish()?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--into-archives");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("bundle.zip"))
+        .stdout(predicate::str::contains("inner"))
+        .stdout(predicate::str::contains("packed.txt"))
+        .stdout(predicate::str::contains("0 directories, 1 files"));
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path());
+    cmd.assert().success().stdout(predicate::str::contains("packed.txt").not());
+    Ok(())
+}
+
+/// Tests that the exclusion size band hides both very small and very large files
+#[test]
+fn test_exclude_size_band() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("empty_dir"))?;
+    fs::write(temp_dir.path().join("tiny.txt"), [0u8; 10])?;
+    fs::write(temp_dir.path().join("medium.txt"), [0u8; 2000])?;
+    fs::write(temp_dir.path().join("huge.txt"), [0u8; 10000])?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path())
+        .arg("--exclude-smaller-than")
+        .arg("1K")
+        .arg("--exclude-larger-than")
+        .arg("5K");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("medium.txt"))
+        .stdout(predicate::str::contains("empty_dir"))
+        .stdout(predicate::str::contains("tiny.txt").not())
+        .stdout(predicate::str::contains("huge.txt").not())
+        .stdout(predicate::str::contains("1 directories, 1 files"));
+    Ok(())
+}
+
+/// Tests that --min-size/--max-size keep only in-range files while parents keep their full size
+#[test]
+fn test_min_max_size() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("dir"))?;
+    fs::write(temp_dir.path().join("dir/small.bin"), [0u8; 100])?;
+    fs::write(temp_dir.path().join("dir/mid.bin"), [0u8; 3000])?;
+    fs::write(temp_dir.path().join("dir/big.bin"), [0u8; 20000])?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--min-size").arg("1KiB").arg("--max-size").arg("10k");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("mid.bin"))
+        .stdout(predicate::str::contains("small.bin").not())
+        .stdout(predicate::str::contains("big.bin").not())
+        .stdout(predicate::str::contains("22.6 KiB"));
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--min-size").arg("3000");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("mid.bin"))
+        .stdout(predicate::str::contains("big.bin"))
+        .stdout(predicate::str::contains("small.bin").not());
+    Ok(())
+}
+
+/// Tests that --no-dotfiles drops dot entries even when --all is set
+#[test]
+fn test_no_dotfiles_with_all() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join(".cache"))?;
+    fs::write(temp_dir.path().join(".cache/inner.txt"), "x")?;
+    fs::write(temp_dir.path().join(".hidden"), "x")?;
+    fs::write(temp_dir.path().join("visible.txt"), "x")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--all").arg("--no-dotfiles");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("visible.txt"))
+        .stdout(predicate::str::contains(".hidden").not())
+        .stdout(predicate::str::contains("inner.txt").not());
+    Ok(())
+}
+
+/// Tests that --depth-markers prefixes each entry with its actual depth
+#[test]
+fn test_depth_markers() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("a/b"))?;
+    fs::write(temp_dir.path().join("a/b/deep.txt"), "x")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--depth-markers");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[1] └── a"))
+        .stdout(predicate::str::contains("[2]     └── b"))
+        .stdout(predicate::str::contains("[3]         └── deep.txt"));
+    Ok(())
+}
+
+/// Tests that --format json writes to --output, and to stdout without it
+#[test]
+fn test_format_json_output() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let out_dir = tempdir()?;
+    fs::write(temp_dir.path().join("a.txt"), "x")?;
+    let out_path = out_dir.path().join("tree.json");
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--format").arg("json").arg("--output").arg(&out_path);
+    cmd.assert().success();
+    let export: serde_json::Value = serde_json::from_str(&fs::read_to_string(&out_path)?)?;
+    assert!(export.to_string().contains("a.txt"));
+
+    let mut cmd = wisu();
+    cmd.current_dir(out_dir.path()).arg(temp_dir.path()).arg("--format").arg("csv");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("path,name,is_dir"))
+        .stdout(predicate::str::contains("a.txt"))
+        .stdout(predicate::str::contains("Export completed").not());
+    assert!(!out_dir.path().join("export.csv").exists());
+    Ok(())
+}
+
+/// Tests that --format json without --output pipes clean JSON to stdout
+#[test]
+fn test_format_json_stdout() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("sub"))?;
+    fs::write(temp_dir.path().join("sub/a.txt"), "x")?;
+
+    let output = wisu().arg(temp_dir.path()).args(["--format", "json"]).output()?;
+    assert!(output.status.success());
+
+    let export: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert!(export.to_string().contains("a.txt"));
+    assert!(String::from_utf8(output.stderr)?.contains("Export completed"));
+    Ok(())
+}
+
+/// Tests that the hierarchical export keeps the view's --natural-sort --reverse order
+#[test]
+fn test_export_preserves_sort_order() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    for name in ["f1", "f2", "f10", "dir3/a9", "dir3/a10", "dir20/b"] {
+        let path = temp_dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, "x")?;
+    }
+    let args = ["--natural-sort", "--reverse"];
+
+    let view = wisu().arg(temp_dir.path()).args(args).output()?;
+    let view_order: Vec<String> = String::from_utf8(view.stdout)?
+        .lines()
+        .filter_map(|line| line.rsplit_once("── ").map(|(_, name)| name.to_string()))
+        .collect();
+
+    let export = wisu().arg(temp_dir.path()).args(args).args(["--format", "json"]).output()?;
+    let export: serde_json::Value = serde_json::from_slice(&export.stdout)?;
+    fn names(node: &serde_json::Value, out: &mut Vec<String>) {
+        for child in node["children"].as_array().into_iter().flatten() {
+            out.push(child["name"].as_str().unwrap().to_string());
+            names(child, out);
+        }
+    }
+    let mut export_order = Vec::new();
+    names(&export, &mut export_order);
+
+    assert_eq!(view_order, ["f10", "f2", "f1", "dir20", "b", "dir3", "a10", "a9"]);
+    assert_eq!(export_order, view_order);
+    Ok(())
+}
+
+/// Tests that --print-config dumps config-file values merged with CLI overrides
+#[test]
+fn test_print_config() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("wisu.toml"), "sort = \"size\"\nlevel = 3\n")?;
+    fs::write(temp_dir.path().join("a.txt"), "x")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("-L").arg("1").arg("--print-config");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("sort = \"size\""))
+        .stdout(predicate::str::contains("level = 1"))
+        .stdout(predicate::str::contains("a.txt").not());
+    Ok(())
+}
+
+/// Tests that a single file path exports as one node with its name and size
+#[test]
+fn test_export_single_file() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let file = temp_dir.path().join("notes.txt");
+    fs::write(&file, [0u8; 42])?;
+
+    let output = wisu().arg(&file).args(["--format", "json"]).output()?;
+    assert!(output.status.success());
+    let export: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(export["name"], "notes.txt");
+    assert_eq!(export["size"], 42);
+    assert_eq!(export["is_dir"], false);
+    assert!(export["children"].is_null());
+
+    // The classic view still requires a directory
+    wisu().arg(&file).assert().failure();
+    Ok(())
+}
+
+/// Tests that a negative level shows only the deepest entries
+#[test]
+fn test_negative_level() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("one/two"))?;
+    fs::write(temp_dir.path().join("top.txt"), "x")?;
+    fs::write(temp_dir.path().join("one/mid.txt"), "x")?;
+    fs::write(temp_dir.path().join("one/two/leaf_a.txt"), "x")?;
+    fs::write(temp_dir.path().join("one/two/leaf_b.txt"), "x")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("-L").arg("-1");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("leaf_a.txt"))
+        .stdout(predicate::str::contains("leaf_b.txt"))
+        .stdout(predicate::str::contains("mid.txt").not())
+        .stdout(predicate::str::contains("top.txt").not())
+        .stdout(predicate::str::contains("0 directories, 2 files"));
+
+    // Levels beyond the tree height are clamped and show everything
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("-L").arg("-10");
+    cmd.assert().success().stdout(predicate::str::contains("2 directories, 4 files"));
+    Ok(())
+}
+
+/// Tests that a YAML export parses back with the expected root name and children
+#[test]
+fn test_export_yaml() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let out_dir = tempdir()?;
+    let root = temp_dir.path().join("proj");
+    fs::create_dir_all(root.join("src"))?;
+    fs::write(root.join("src/main.rs"), "fn main() {}")?;
+    fs::write(root.join("README.md"), "hi")?;
+
+    let mut cmd = wisu();
+    cmd.current_dir(out_dir.path()).arg(&root).arg("-o").arg("yaml");
+    cmd.assert().success();
+
+    let export: serde_yaml::Value =
+        serde_yaml::from_str(&fs::read_to_string(out_dir.path().join("export.yaml"))?)?;
+    assert_eq!(export["name"].as_str(), Some("proj"));
+    assert_eq!(export["children"].as_sequence().map(Vec::len), Some(2));
+    Ok(())
+}
+
+/// Tests that --collapse-single-child folds `a/b/c` into one line above its file
+#[test]
+fn test_collapse_single_child() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("a/b/c"))?;
+    fs::write(temp_dir.path().join("a/b/c/file"), "x")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--collapse-single-child");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("└── a/b/c\n    └── file"))
+        .stdout(predicate::str::contains("3 directories, 1 files"));
+    Ok(())
+}
+
+/// Tests that --only-ext keeps only the listed extensions and their ancestor directories
+#[test]
+fn test_only_ext() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("src"))?;
+    fs::create_dir_all(temp_dir.path().join("docs"))?;
+    fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}")?;
+    fs::write(temp_dir.path().join("Cargo.TOML"), "[package]")?;
+    fs::write(temp_dir.path().join("docs/guide.md"), "# guide")?;
+    fs::write(temp_dir.path().join("notes.txt"), "x")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--only-ext").arg("rs, .toml");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs"))
+        .stdout(predicate::str::contains("Cargo.TOML"))
+        .stdout(predicate::str::contains("src"))
+        .stdout(predicate::str::contains("guide.md").not())
+        .stdout(predicate::str::contains("docs").not())
+        .stdout(predicate::str::contains("notes.txt").not());
+    Ok(())
+}
+
+/// Tests that --stream writes a parseable flat JSON array and the full CSV
+#[test]
+fn test_export_stream() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let out_dir = tempdir()?;
+    for d in 0..4 {
+        let dir = temp_dir.path().join(format!("d{d}"));
+        fs::create_dir(&dir)?;
+        for f in 0..75 {
+            fs::write(dir.join(format!("f{f}.txt")), "x")?;
+        }
+    }
+
+    let json_path = out_dir.path().join("tree.json");
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).args(["--format", "json", "--stream", "--output"]).arg(&json_path);
+    cmd.assert().success();
+    let nodes: Vec<serde_json::Value> = serde_json::from_str(&fs::read_to_string(&json_path)?)?;
+    assert_eq!(nodes.len(), 304);
+    assert!(nodes.iter().any(|n| n["path"].as_str().unwrap().ends_with("d3/f74.txt")));
+
+    let mut cmd = wisu();
+    let output = cmd.arg(temp_dir.path()).args(["--format", "csv", "--stream"]).output()?;
+    assert_eq!(String::from_utf8(output.stdout)?.lines().count(), 305);
+    Ok(())
+}
+
+/// Tests that an NDJSON export has one parseable object per flat node
+#[test]
+fn test_export_ndjson() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("sub"))?;
+    fs::write(temp_dir.path().join("sub/a.txt"), "x")?;
+    fs::write(temp_dir.path().join("b.txt"), "x")?;
+    fs::write(temp_dir.path().join("c.txt"), "x")?;
+
+    let mut cmd = wisu();
+    let output = cmd.arg(temp_dir.path()).args(["--format", "jsonl"]).output()?;
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let nodes: Vec<serde_json::Value> =
+        stdout.lines().map(serde_json::from_str).collect::<Result<_, _>>()?;
+    assert_eq!(nodes.len(), 4);
+    assert!(nodes.iter().all(|n| n["children"].is_null()));
+    Ok(())
+}
+
+/// Tests that --fail-if-empty exits with 1 when the filters match no files
+#[test]
+fn test_fail_if_empty() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("sub"))?;
+    fs::write(temp_dir.path().join("sub/a.txt"), "x")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).args(["--only-ext", "rs", "--fail-if-empty"]);
+    cmd.assert()
+        .code(1)
+        .stdout(predicate::str::contains("0 directories, 0 files"))
+        .stderr(predicate::str::is_empty());
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).args(["--only-ext", "txt", "--fail-if-empty"]);
+    cmd.assert().success().stdout(predicate::str::contains("a.txt"));
+
+    // Exports honor it too, after writing the (empty) export
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).args(["--only-ext", "rs", "--fail-if-empty", "--format", "json"]);
+    cmd.assert().code(1).stdout(predicate::str::contains("\"file_count\": 0"));
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).args(["--only-ext", "txt", "--fail-if-empty", "--format", "csv"]);
+    cmd.assert().success().stdout(predicate::str::contains("a.txt"));
+    Ok(())
+}
+
+/// Tests that --relative-to rewrites export paths against the chosen base
+#[test]
+fn test_export_relative_to() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let repo = temp_dir.path().join("repo");
+    fs::create_dir_all(repo.join("proj/sub"))?;
+    fs::create_dir(temp_dir.path().join("elsewhere"))?;
+    fs::write(repo.join("proj/sub/a.txt"), "x")?;
+
+    let paths = |base: &std::path::Path| -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let output = wisu()
+            .arg(repo.join("proj"))
+            .args(["--format", "ndjson", "--relative-to"])
+            .arg(base)
+            .output()?;
+        let stdout = String::from_utf8(output.stdout)?;
+        Ok(stdout
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .map(|node| node["path"].as_str().unwrap().to_string())
+            .collect())
+    };
+
+    assert_eq!(paths(&repo)?, ["./proj/sub", "./proj/sub/a.txt"]);
+
+    // Not an ancestor: absolute paths
+    let absolute = fs::canonicalize(repo.join("proj/sub/a.txt"))?;
+    assert!(paths(&temp_dir.path().join("elsewhere"))?.contains(&absolute.display().to_string()));
+    Ok(())
+}
+
+/// Tests that --stats-json writes the counts, throughput and depth histogram, for exports too
+#[test]
+fn test_stats_json_sidecar() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let out_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("a/b"))?;
+    fs::write(temp_dir.path().join("a/b/deep.txt"), [0u8; 10])?;
+    fs::write(temp_dir.path().join("a/mid.txt"), [0u8; 20])?;
+    fs::write(temp_dir.path().join("top.txt"), [0u8; 30])?;
+    let sidecar = out_dir.path().join("stats.json");
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--stats-json").arg(&sidecar);
+    cmd.assert().success().stdout(predicate::str::contains("top.txt"));
+
+    let stats: serde_json::Value = serde_json::from_str(&fs::read_to_string(&sidecar)?)?;
+    assert_eq!(stats["dirs"], 2);
+    assert_eq!(stats["files"], 3);
+    assert_eq!(stats["size"], 60);
+    assert_eq!(stats["depths"], serde_json::json!({ "1": 2, "2": 2, "3": 1 }));
+    assert!(stats["duration_ms"].as_f64().is_some());
+    assert!(stats["entries_per_sec"].as_f64().is_some_and(|rate| rate > 0.0));
+
+    // Exports without a document root get their scan stats from the sidecar
+    for format in ["csv", "tsv", "ndjson"] {
+        let mut cmd = wisu();
+        cmd.arg(temp_dir.path()).args(["--format", format, "--stats-json"]).arg(&sidecar);
+        cmd.assert().success().stdout(predicate::str::contains("top.txt"));
+
+        let stats: serde_json::Value = serde_json::from_str(&fs::read_to_string(&sidecar)?)?;
+        assert_eq!(stats["dirs"], 2, "{format}");
+        assert_eq!(stats["files"], 3, "{format}");
+        assert_eq!(stats["size"], 60, "{format}");
+        assert_eq!(stats["entries"], 5, "{format}");
+        assert!(stats["duration_ms"].as_f64().is_some_and(|ms| ms > 0.0), "{format}");
+    }
+    Ok(())
+}
+
+/// Tests that --follow-symlinks enters linked directories but stops at cycles
+#[cfg(unix)]
+#[test]
+fn test_follow_symlinks_cycle() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let outside = tempdir()?;
+    fs::create_dir(temp_dir.path().join("sub"))?;
+    fs::write(temp_dir.path().join("sub/a.txt"), "x")?;
+    fs::write(outside.path().join("linked.txt"), "x")?;
+    std::os::unix::fs::symlink(temp_dir.path(), temp_dir.path().join("sub/back"))?;
+    std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("ext"))?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--follow-symlinks");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output)?;
+
+    assert!(stdout.contains("linked.txt"));
+    assert_eq!(stdout.matches("a.txt").count(), 1);
+    Ok(())
+}
+
+/// Tests that --follow-symlinks keeps both a directory and a sibling link to it
+#[cfg(unix)]
+#[test]
+fn test_follow_symlinks_sibling_link() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("zreal"))?;
+    fs::write(temp_dir.path().join("zreal/inner.txt"), "x")?;
+    std::os::unix::fs::symlink(temp_dir.path().join("zreal"), temp_dir.path().join("alink"))?;
+
+    for threads in [None, Some("4")] {
+        let mut cmd = wisu();
+        cmd.arg(temp_dir.path()).arg("--follow-symlinks");
+        if let Some(threads) = threads {
+            cmd.args(["--threads", threads]);
+        }
+        let output = cmd.assert().success().get_output().stdout.clone();
+        let stdout = String::from_utf8(output)?;
+
+        assert!(stdout.contains("alink"), "{stdout}");
+        assert!(stdout.contains("zreal"), "{stdout}");
+        // The link is a leaf: the shared contents are listed once, under the real directory
+        assert_eq!(stdout.matches("inner.txt").count(), 1, "{stdout}");
+    }
+    Ok(())
+}
+
+/// Tests that --git-status styles ignored, untracked and tracked files distinctly
+#[test]
+fn test_git_status_colors() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    fs::write(root.join(".gitignore"), "*.log\n")?;
+    fs::write(root.join("tracked.txt"), "x")?;
+
+    let repo = git2::Repository::init(root)?;
+    let mut index = repo.index()?;
+    index.add_all(["*"], git2::IndexAddOption::DEFAULT, None)?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = git2::Signature::now("wisu", "wisu@example.com")?;
+    repo.commit(Some("HEAD"), &signature, &signature, "initial", &tree, &[])?;
+    fs::write(root.join("ignored.log"), "x")?;
+    fs::write(root.join("untracked.txt"), "x")?;
+
+    let mut cmd = wisu();
+    cmd.arg(root).arg("--git-status").env("CLICOLOR_FORCE", "1").env_remove("LS_COLORS");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[2mignored.log"))
+        .stdout(predicate::str::contains("\x1b[32muntracked.txt"))
+        .stdout(predicate::str::contains("\x1b[37mtracked.txt"));
+    Ok(())
+}
+
+/// Tests that --max-total-size stops the scan and prints a partial tree, noticed on stderr
+#[test]
+fn test_max_total_size() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+        fs::write(temp_dir.path().join(name), "x".repeat(100))?;
+    }
+
+    for extra in [&[][..], &["--flat"], &["--format", "long"]] {
+        let output =
+            wisu().arg(temp_dir.path()).args(["--max-total-size", "250"]).args(extra).output()?;
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout)?;
+
+        let listed =
+            ["a.txt", "b.txt", "c.txt", "d.txt"].iter().filter(|n| stdout.contains(*n)).count();
+        assert_eq!(listed, 2, "{extra:?}");
+        // The notice stays out of piped output
+        assert!(!stdout.contains("Scan stopped"), "{extra:?}");
+        assert!(String::from_utf8(output.stderr)?.contains("Scan stopped at --max-total-size"));
+    }
+    Ok(())
+}
+
+/// Tests that --dirs-only stats and export totals count only the listed directories
+#[test]
+fn test_dirs_only_stats() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("a/b"))?;
+    fs::write(temp_dir.path().join("a/f.txt"), "hello")?;
+    fs::write(temp_dir.path().join("g.txt"), "x")?;
+    let out_dir = tempdir()?;
+    let sidecar = out_dir.path().join("stats.json");
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--dirs-only").arg("--stats").arg("--stats-json").arg(&sidecar);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert!(String::from_utf8(output)?.contains("2 directories, 0 files"));
+
+    let stats: serde_json::Value = serde_json::from_str(&fs::read_to_string(&sidecar)?)?;
+    assert_eq!((stats["dirs"].as_u64(), stats["files"].as_u64()), (Some(2), Some(0)));
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--dirs-only").arg("--format").arg("json");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let root: serde_json::Value = serde_json::from_slice(&output)?;
+    assert_eq!((root["dir_count"].as_u64(), root["file_count"].as_u64()), (Some(2), Some(0)));
+    Ok(())
+}
+
+/// Tests that --ascii output contains no non-ASCII bytes
+#[test]
+fn test_ascii_connectors() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir_all(temp_dir.path().join("a/b"))?;
+    fs::write(temp_dir.path().join("a/b/deep.txt"), "x")?;
+    fs::write(temp_dir.path().join("a/x.txt"), "x")?;
+    fs::write(temp_dir.path().join("z.txt"), "x")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--ascii");
+    let output = cmd.assert().success().get_output().stdout.clone();
+
+    assert!(output.is_ascii(), "{}", String::from_utf8_lossy(&output));
+    let stdout = String::from_utf8(output)?;
+    assert!(stdout.contains("|   |-- b") || stdout.contains("|   `-- b"));
+    assert!(stdout.contains("`-- z.txt"));
+    Ok(())
+}
+
+/// Tests that --flat prints one relative path per line without tree art
+#[test]
+fn test_flat_paths() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("dir1"))?;
+    fs::write(temp_dir.path().join("dir1/b.txt"), "x")?;
+    fs::write(temp_dir.path().join("a.txt"), "x")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--flat").arg("--size");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output)?;
+
+    assert!(stdout.lines().any(|line| line.split_whitespace().next() == Some("dir1/b.txt")));
+    assert!(stdout.lines().any(|line| line.starts_with("a.txt")));
+    assert!(!stdout.contains("├──") && !stdout.contains("└──"));
+    Ok(())
+}
+
+/// Tests that --icons-only-dirs puts icons on directories but not on files
+#[test]
+fn test_icons_only_dirs() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("a.txt"), "x")?;
+    fs::create_dir(temp_dir.path().join("sub"))?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--icons-only-dirs");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output)?;
+
+    let line = |name: &str| stdout.lines().find(|l| l.ends_with(name)).unwrap_or_default();
+    assert!(line("a.txt").ends_with("── a.txt"));
+    assert!(line("sub").contains("sub") && !line("sub").ends_with("── sub"));
+    Ok(())
+}
+
+/// Tests that the global config is layered under the project `wisu.toml` and CLI flags
+#[test]
+fn test_layered_config() -> Result<(), Box<dyn std::error::Error>> {
+    let config_home = tempdir()?;
+    fs::create_dir(config_home.path().join("wisu"))?;
+    fs::write(
+        config_home.path().join("wisu/wisu.toml"),
+        "sort = \"size\"\nlevel = 1\nsize = true\n[tui]\nsearch = \"prefix\"\n[tui.expand]\nsrc = 1\n",
+    )?;
+    let project = tempdir()?;
+    fs::write(
+        project.path().join("wisu.toml"),
+        "level = 2\nreverse = true\n[tui]\nsearch = \"fuzzy\"\n[tui.expand]\ndocs = 2\n",
+    )?;
+
+    let print_config = |extra: &[&str]| -> Result<String, Box<dyn std::error::Error>> {
+        let output = wisu()
+            .arg(project.path())
+            .args(extra)
+            .arg("--print-config")
+            .env("XDG_CONFIG_HOME", config_home.path())
+            .env("APPDATA", config_home.path())
+            .output()?;
+        assert!(output.status.success());
+        Ok(String::from_utf8(output.stdout)?)
+    };
+
+    let merged = print_config(&[])?;
+    assert!(merged.contains("sort = \"size\""));
+    assert!(merged.contains("size = true"));
+    assert!(merged.contains("reverse = true"));
+    assert!(merged.contains("level = 2"));
+    // `[tui]` conflicts go to the project; expansion rules from both layers apply
+    assert!(merged.contains("search = \"fuzzy\""));
+    assert!(merged.contains("src = 1"));
+    assert!(merged.contains("docs = 2"));
+
+    assert!(print_config(&["-L", "3"])?.contains("level = 3"));
+    Ok(())
+}
+
+/// Tests that --no-size-for-dirs drops directory annotations but keeps file sizes
+#[test]
+fn test_no_size_for_dirs() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("sub"))?;
+    fs::write(temp_dir.path().join("sub/a.txt"), "hello")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--info").arg("--no-size-for-dirs");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output)?;
+
+    let line = |name: &str| stdout.lines().find(|l| l.contains(name)).unwrap_or_default();
+    assert!(line("sub").ends_with("── sub"));
+    assert!(line("a.txt").ends_with("a.txt  [ 5 B ]"));
+    Ok(())
+}
+
+/// Tests that --bytes shows plain byte counts and the raw total in the stats line
+#[test]
+fn test_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("big.bin"), vec![0u8; 1536 * 1024])?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("-s").arg("--stats").arg("--bytes");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("big.bin (1572864)"))
+        .stdout(predicate::str::contains("1.5 MiB (1572864 bytes), 0 directories, 1 files"));
+    Ok(())
+}
+
+/// Tests that --owner and the owner column show the name of the user owning the files
+#[cfg(unix)]
+#[test]
+fn test_owner() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("a.txt"), "x")?;
+    let user = uzers::get_current_username().ok_or("no current user")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("-p").arg("--owner");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output)?;
+
+    let line = stdout.lines().find(|l| l.contains("a.txt")).unwrap_or_default();
+    assert!(line.contains(&format!(" {} ", user.to_string_lossy())), "{line}");
+
+    // As a column, alongside the --info suffix of the name; --owner alone is not one
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).args(["--columns", "owner,name", "--info"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output)?;
+    let line = stdout.lines().find(|l| l.contains("a.txt")).unwrap_or_default();
+    assert!(line.starts_with(&*user.to_string_lossy()), "{line}");
+    assert!(line.ends_with("a.txt  [ 1 B ]"), "{line}");
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).args(["--columns", "name", "--owner"]);
+    cmd.assert().success().stderr(predicate::str::contains("add the owner column"));
+
+    // Tables get the column from --owner
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).args(["--format", "table", "--owner"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output)?;
+    let header = stdout.lines().find(|l| l.starts_with("PATH")).unwrap_or_default();
+    assert!(header.find("PERMS").unwrap() < header.find("OWNER").unwrap(), "{header}");
+    Ok(())
+}
+
+/// Tests that --dedupe-by-content reports files with identical content
+#[test]
+fn test_dedupe_by_content() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::create_dir(temp_dir.path().join("copy"))?;
+    fs::write(temp_dir.path().join("original.txt"), "same content")?;
+    fs::write(temp_dir.path().join("copy/original.txt"), "same content")?;
+    fs::write(temp_dir.path().join("other.txt"), "diff content")?;
+
+    let mut cmd = wisu();
+    cmd.arg(temp_dir.path()).arg("--dedupe-by-content");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "2 copies of 12 B, 12 B wasted\n  copy/original.txt\n  original.txt\n",
+        ))
+        .stdout(predicate::str::contains("other.txt").not())
+        .stdout(predicate::str::contains("1 duplicate sets, 12 B wasted"));
+    Ok(())
+}
+
+/// Tests that --watch with an export format emits a new snapshot after a change,
+/// and stops on Ctrl-C
+#[test]
+fn test_watch_export_snapshots() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("first.txt"), "x")?;
+
+    let mut child = wisu()
+        .arg(temp_dir.path())
+        .args(["--watch", "--format", "ndjson"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().ok_or("no stdout")?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Lines received until one mentions `name`, or None on timeout
+    let wait_for = |name: &str| {
+        let mut lines = Vec::new();
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while let Some(left) = deadline.checked_duration_since(std::time::Instant::now()) {
+            match rx.recv_timeout(left) {
+                Ok(line) if line.contains(name) => return Some(lines),
+                Ok(line) => lines.push(line),
+                Err(_) => return None,
+            }
+        }
+        None
+    };
+
+    let initial = wait_for("first.txt");
+    fs::write(temp_dir.path().join("second.txt"), "y")?;
+    let updated = wait_for("second.txt");
+
+    // Ctrl-C ends the watch cleanly between snapshots
+    #[cfg(unix)]
+    {
+        Command::new("kill").args(["-INT", &child.id().to_string()]).status()?;
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        let status = loop {
+            match child.try_wait()? {
+                Some(status) => break status,
+                None if std::time::Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(50))
+                }
+                None => return Err("watch kept running after SIGINT".into()),
+            }
+        };
+        assert!(status.success(), "{status}");
+    }
+    #[cfg(not(unix))]
+    child.kill()?;
+    child.wait()?;
+
+    // Each snapshot opens with its own header record
+    let initial = initial.ok_or("no initial snapshot")?;
+    assert!(initial.contains(&r#"{"snapshot":1}"#.to_string()), "{initial:?}");
+    let updated = updated.ok_or("no snapshot after the change")?;
+    assert!(updated.iter().any(|line| line.starts_with(r#"{"snapshot":"#)), "{updated:?}");
+    Ok(())
+}
+
+/// Tests that a TSV export has a tab-separated header and unquoted rows
+#[test]
+fn test_export_tsv() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("a b.txt"), "hello")?;
+
+    let mut cmd = wisu();
+    let output = cmd.arg(temp_dir.path()).args(["--format", "tsv"]).output()?;
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("path\tname\tis_dir\tsize\tdir_count\tfile_count\tpermissions"));
+    let row = lines.find(|l| l.contains("a b.txt")).ok_or("missing row")?;
+    assert_eq!(row.split('\t').collect::<Vec<_>>()[1..4], ["a b.txt", "false", "5"]);
+    Ok(())
+}